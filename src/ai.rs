@@ -0,0 +1,171 @@
+use crate::alphabet::{Alphabet, Letter};
+use crate::game::{Action, GameState};
+use crate::movegen::{generate_moves, Play};
+use std::cmp::Reverse;
+use std::fmt;
+use std::str::FromStr;
+
+// Most an intermediate computer player will try to score in one turn
+const INTERMEDIATE_SCORE_CAP: u32 = 20;
+// Longest word a beginner will play when the game has no common word list
+const BEGINNER_WORD_LENGTH: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    // Sticks to common words and picks a middling play
+    Beginner,
+    // Plays the best it can find without going over a score cap
+    Intermediate,
+    // Plays for score and for the tiles it keeps for later turns
+    Expert,
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "beginner" => Ok(Difficulty::Beginner),
+            "intermediate" => Ok(Difficulty::Intermediate),
+            "expert" => Ok(Difficulty::Expert),
+            _ => Err(format!("Unknown difficulty {:?}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difficulty::Beginner => write!(f, "beginner"),
+            Difficulty::Intermediate => write!(f, "intermediate"),
+            Difficulty::Expert => write!(f, "expert"),
+        }
+    }
+}
+
+// Picks a play for the seat the way its difficulty would, falling back to swapping the whole
+// hand or passing when there is nothing it wants to play
+pub fn choose_action(game: &GameState, seat: usize, difficulty: Difficulty) -> Action {
+    let hand = match game.player(seat) {
+        Some(player) => player.hand(),
+        None => return Action::Pass,
+    };
+    let plays = game
+        .dictionary()
+        .map(|dictionary| generate_moves(game.board(), game.rules(), hand, dictionary))
+        .unwrap_or_default();
+    let chosen = match difficulty {
+        Difficulty::Beginner => beginner_play(game, plays),
+        Difficulty::Intermediate => intermediate_play(plays),
+        Difficulty::Expert => expert_play(game, hand, plays),
+    };
+    match chosen {
+        Some(play) => Action::Play(play.placements),
+        None if game.bag().len() >= game.rules().rack_size && !hand.is_empty() => {
+            Action::Exchange(hand.to_vec())
+        }
+        None => Action::Pass,
+    }
+}
+
+// The best scoring plays for the seat's hand, highest first
+pub fn hints(game: &GameState, seat: usize, count: usize) -> Vec<Play> {
+    let hand = match game.player(seat) {
+        Some(player) => player.hand(),
+        None => return vec![],
+    };
+    let mut plays = match game.dictionary() {
+        Some(dictionary) => generate_moves(game.board(), game.rules(), hand, dictionary),
+        None => return vec![],
+    };
+    plays.sort_by_key(|play| Reverse(play.score.total));
+    plays.truncate(count);
+    plays
+}
+
+fn beginner_play(game: &GameState, mut plays: Vec<Play>) -> Option<Play> {
+    plays.retain(|play| {
+        play.score
+            .words
+            .iter()
+            .all(|word| match game.common_words() {
+                Some(common_words) => common_words.contains(&word.letters),
+                None => word.letters.len() <= BEGINNER_WORD_LENGTH,
+            })
+    });
+    plays.sort_by_key(|play| play.score.total);
+    let middle = plays.len() / 2;
+    plays.into_iter().nth(middle)
+}
+
+fn intermediate_play(plays: Vec<Play>) -> Option<Play> {
+    let (under, over): (Vec<Play>, Vec<Play>) = plays
+        .into_iter()
+        .partition(|play| play.score.total <= INTERMEDIATE_SCORE_CAP);
+    match under.into_iter().max_by_key(|play| play.score.total) {
+        Some(play) => Some(play),
+        None => over.into_iter().min_by_key(|play| play.score.total),
+    }
+}
+
+fn expert_play(game: &GameState, hand: &[Letter], plays: Vec<Play>) -> Option<Play> {
+    // With nothing left to draw the tiles kept only count against the player
+    let bag_empty = game.bag().is_empty();
+    plays.into_iter().max_by_key(|play| {
+        let leave = leave_after(hand, play);
+        let leave_value = if bag_empty {
+            -(leave.len() as i32)
+        } else {
+            leave_value(&game.rules().alphabet, &leave)
+        };
+        play.score.total as i32 + leave_value
+    })
+}
+
+fn leave_after(hand: &[Letter], play: &Play) -> Vec<Letter> {
+    let mut leave = hand.to_vec();
+    for placement in &play.placements {
+        let used = placement.tile.used();
+        if let Some(index) = leave.iter().position(|tile| *tile == used) {
+            leave.swap_remove(index);
+        }
+    }
+    leave
+}
+
+// A rough worth for the tiles kept after a play. Blanks and S are worth holding on to, awkward
+// letters, duplicates and a lopsided mix of vowels and consonants are not. The letter values
+// are tuned for English and other alphabets only get the blank, duplicate and balance terms
+// for letters they share with it.
+fn leave_value(alphabet: &Alphabet, leave: &[Letter]) -> i32 {
+    let letters: Vec<&str> = leave.iter().map(|tile| alphabet.display(*tile)).collect();
+    let mut value: i32 = leave
+        .iter()
+        .zip(&letters)
+        .map(|(tile, letter)| match *letter {
+            _ if tile.is_blank() => 25,
+            "S" => 8,
+            "X" | "Z" => 3,
+            "E" | "R" => 2,
+            "A" | "D" | "H" | "I" | "L" | "N" | "T" => 1,
+            "J" | "U" | "V" | "W" => -3,
+            "Q" => -7,
+            _ => 0,
+        })
+        .sum();
+    for (i, tile) in leave.iter().enumerate() {
+        if !tile.is_blank() && leave[..i].contains(tile) {
+            value -= 3;
+        }
+    }
+    let vowels = letters
+        .iter()
+        .filter(|letter| matches!(**letter, "A" | "E" | "I" | "O" | "U"))
+        .count() as i32;
+    let consonants = leave.iter().filter(|tile| !tile.is_blank()).count() as i32 - vowels;
+    value -= 2 * ((vowels - consonants).abs() - 1).max(0);
+    if letters.contains(&"Q") && !letters.contains(&"U") {
+        value -= 5;
+    }
+    value
+}
@@ -0,0 +1,437 @@
+// Letters are kept in bitmasks by the move generator, so an alphabet can't be any bigger
+pub const MAX_LETTERS: usize = 63;
+
+// (display, count, score) for each letter in alphabet order
+const ENGLISH: [(&str, u32, u32); 26] = [
+    ("A", 9, 1),
+    ("B", 2, 3),
+    ("C", 2, 3),
+    ("D", 4, 2),
+    ("E", 12, 1),
+    ("F", 2, 4),
+    ("G", 3, 2),
+    ("H", 2, 4),
+    ("I", 9, 1),
+    ("J", 1, 8),
+    ("K", 1, 5),
+    ("L", 4, 1),
+    ("M", 2, 3),
+    ("N", 6, 1),
+    ("O", 8, 1),
+    ("P", 2, 3),
+    ("Q", 1, 10),
+    ("R", 6, 1),
+    ("S", 4, 1),
+    ("T", 6, 1),
+    ("U", 4, 1),
+    ("V", 2, 4),
+    ("W", 2, 4),
+    ("X", 1, 8),
+    ("Y", 2, 4),
+    ("Z", 1, 10),
+];
+
+const SPANISH: [(&str, u32, u32); 28] = [
+    ("A", 12, 1),
+    ("B", 2, 3),
+    ("C", 4, 3),
+    ("CH", 1, 5),
+    ("D", 5, 2),
+    ("E", 12, 1),
+    ("F", 1, 4),
+    ("G", 2, 2),
+    ("H", 2, 4),
+    ("I", 6, 1),
+    ("J", 1, 8),
+    ("L", 4, 1),
+    ("LL", 1, 8),
+    ("M", 2, 3),
+    ("N", 5, 1),
+    ("Ñ", 1, 8),
+    ("O", 9, 1),
+    ("P", 2, 3),
+    ("Q", 1, 5),
+    ("R", 5, 1),
+    ("RR", 1, 8),
+    ("S", 6, 1),
+    ("T", 4, 1),
+    ("U", 5, 1),
+    ("V", 1, 4),
+    ("X", 1, 8),
+    ("Y", 1, 4),
+    ("Z", 1, 10),
+];
+
+const GERMAN: [(&str, u32, u32); 29] = [
+    ("A", 5, 1),
+    ("Ä", 1, 6),
+    ("B", 2, 3),
+    ("C", 2, 4),
+    ("D", 4, 1),
+    ("E", 15, 1),
+    ("F", 2, 4),
+    ("G", 3, 2),
+    ("H", 4, 2),
+    ("I", 6, 1),
+    ("J", 1, 6),
+    ("K", 2, 4),
+    ("L", 3, 2),
+    ("M", 4, 3),
+    ("N", 9, 1),
+    ("O", 3, 2),
+    ("Ö", 1, 8),
+    ("P", 1, 4),
+    ("Q", 1, 10),
+    ("R", 6, 1),
+    ("S", 7, 1),
+    ("T", 6, 1),
+    ("U", 6, 1),
+    ("Ü", 1, 6),
+    ("V", 1, 6),
+    ("W", 1, 3),
+    ("X", 1, 8),
+    ("Y", 1, 10),
+    ("Z", 1, 3),
+];
+
+const POLISH: [(&str, u32, u32); 32] = [
+    ("A", 9, 1),
+    ("Ą", 1, 5),
+    ("B", 2, 3),
+    ("C", 3, 2),
+    ("Ć", 1, 6),
+    ("D", 3, 2),
+    ("E", 7, 1),
+    ("Ę", 1, 5),
+    ("F", 1, 5),
+    ("G", 2, 3),
+    ("H", 2, 3),
+    ("I", 8, 1),
+    ("J", 2, 3),
+    ("K", 3, 2),
+    ("L", 3, 2),
+    ("Ł", 2, 3),
+    ("M", 3, 2),
+    ("N", 5, 1),
+    ("Ń", 1, 7),
+    ("O", 6, 1),
+    ("Ó", 1, 5),
+    ("P", 3, 2),
+    ("R", 4, 1),
+    ("S", 4, 1),
+    ("Ś", 1, 5),
+    ("T", 3, 2),
+    ("U", 2, 3),
+    ("W", 4, 1),
+    ("Y", 4, 2),
+    ("Z", 5, 1),
+    ("Ź", 1, 9),
+    ("Ż", 1, 5),
+];
+
+// Welsh tiles include eight digraphs. Words are parsed by longest match, so a word list can't
+// spell N followed by G, "BANGOR" is always read as B-A-NG-O-R.
+const WELSH: [(&str, u32, u32); 29] = [
+    ("A", 9, 1),
+    ("B", 2, 3),
+    ("C", 2, 4),
+    ("CH", 1, 5),
+    ("D", 6, 1),
+    ("DD", 4, 2),
+    ("E", 7, 1),
+    ("F", 3, 2),
+    ("FF", 1, 4),
+    ("G", 3, 2),
+    ("NG", 1, 10),
+    ("H", 2, 4),
+    ("I", 7, 1),
+    ("J", 1, 10),
+    ("L", 3, 2),
+    ("LL", 1, 5),
+    ("M", 2, 3),
+    ("N", 7, 1),
+    ("O", 6, 1),
+    ("P", 2, 4),
+    ("PH", 1, 8),
+    ("R", 7, 1),
+    ("RH", 1, 8),
+    ("S", 1, 4),
+    ("T", 2, 3),
+    ("TH", 1, 4),
+    ("U", 3, 2),
+    ("W", 5, 1),
+    ("Y", 7, 1),
+];
+
+// Identifies a tile within an alphabet, letters are numbered from 1 in alphabet order and 0 is
+// the blank
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Letter(u8);
+
+impl Letter {
+    pub const BLANK: Letter = Letter(0);
+
+    pub fn new(index: u8) -> Self {
+        Letter(index)
+    }
+
+    pub fn index(&self) -> u8 {
+        self.0
+    }
+
+    pub fn is_blank(&self) -> bool {
+        *self == Letter::BLANK
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LetterInfo {
+    // What is printed on the tile, some languages use two or three characters like "LL"
+    pub display: String,
+    // How many of the tile the bag starts with
+    pub count: u32,
+    pub score: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alphabet {
+    name: String,
+    blanks: u32,
+    letters: Vec<LetterInfo>,
+}
+
+impl Alphabet {
+    pub fn new(name: &str, blanks: u32) -> Self {
+        Alphabet {
+            name: name.to_string(),
+            blanks,
+            letters: vec![],
+        }
+    }
+
+    fn from_table(name: &str, table: &[(&str, u32, u32)]) -> Self {
+        let mut alphabet = Alphabet::new(name, 2);
+        for (display, count, score) in table {
+            alphabet.add_letter(display, *count, *score);
+        }
+        alphabet
+    }
+
+    pub fn english() -> Self {
+        Alphabet::from_table("english", &ENGLISH)
+    }
+
+    pub fn spanish() -> Self {
+        Alphabet::from_table("spanish", &SPANISH)
+    }
+
+    pub fn german() -> Self {
+        Alphabet::from_table("german", &GERMAN)
+    }
+
+    pub fn polish() -> Self {
+        Alphabet::from_table("polish", &POLISH)
+    }
+
+    pub fn welsh() -> Self {
+        Alphabet::from_table("welsh", &WELSH)
+    }
+
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "english" => Some(Alphabet::english()),
+            "spanish" => Some(Alphabet::spanish()),
+            "german" => Some(Alphabet::german()),
+            "polish" => Some(Alphabet::polish()),
+            "welsh" => Some(Alphabet::welsh()),
+            _ => None,
+        }
+    }
+
+    // Adds the next letter in alphabet order, panics if the alphabet is already full
+    pub fn add_letter(&mut self, display: &str, count: u32, score: u32) -> Letter {
+        assert!(
+            self.letters.len() < MAX_LETTERS,
+            "Alphabets can have at most {} letters",
+            MAX_LETTERS
+        );
+        self.letters.push(LetterInfo {
+            display: display.to_uppercase(),
+            count,
+            score,
+        });
+        Letter(self.letters.len() as u8)
+    }
+
+    // Replaces or adds the letter at a given position, used by clients learning the alphabet
+    // one letter at a time from the server
+    pub fn set_letter(&mut self, letter: Letter, display: &str, score: u32) {
+        if letter.is_blank() || letter.index() as usize > MAX_LETTERS {
+            return;
+        }
+        let index = letter.index() as usize - 1;
+        if self.letters.len() <= index {
+            self.letters.resize(
+                index + 1,
+                LetterInfo {
+                    display: String::new(),
+                    count: 0,
+                    score: 0,
+                },
+            );
+        }
+        self.letters[index] = LetterInfo {
+            display: display.to_uppercase(),
+            count: 0,
+            score,
+        };
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn blanks(&self) -> u32 {
+        self.blanks
+    }
+
+    pub fn set_blanks(&mut self, blanks: u32) {
+        self.blanks = blanks;
+    }
+
+    pub fn len(&self) -> usize {
+        self.letters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.letters.is_empty()
+    }
+
+    // Every letter in alphabet order, not including the blank
+    pub fn letters(&self) -> impl Iterator<Item = Letter> {
+        (1..=self.letters.len() as u8).map(Letter)
+    }
+
+    pub fn get(&self, letter: Letter) -> Option<&LetterInfo> {
+        if letter.is_blank() {
+            None
+        } else {
+            self.letters.get(letter.index() as usize - 1)
+        }
+    }
+
+    pub fn get_mut(&mut self, letter: Letter) -> Option<&mut LetterInfo> {
+        if letter.is_blank() {
+            None
+        } else {
+            self.letters.get_mut(letter.index() as usize - 1)
+        }
+    }
+
+    pub fn contains(&self, letter: Letter) -> bool {
+        self.get(letter).is_some()
+    }
+
+    // The blank is shown as "?"
+    pub fn display(&self, letter: Letter) -> &str {
+        match self.get(letter) {
+            Some(info) => &info.display,
+            None => "?",
+        }
+    }
+
+    pub fn score(&self, letter: Letter) -> u32 {
+        self.get(letter).map_or(0, |info| info.score)
+    }
+
+    pub fn count(&self, letter: Letter) -> u32 {
+        if letter.is_blank() {
+            self.blanks
+        } else {
+            self.get(letter).map_or(0, |info| info.count)
+        }
+    }
+
+    // The full starting distribution, blanks first then every letter in alphabet order
+    pub fn bag(&self) -> Vec<Letter> {
+        let mut bag = vec![Letter::BLANK; self.blanks as usize];
+        for letter in self.letters() {
+            bag.extend(std::iter::repeat_n(letter, self.count(letter) as usize));
+        }
+        bag
+    }
+
+    pub fn letter(&self, display: &str) -> Option<Letter> {
+        let display = display.trim().to_uppercase();
+        self.letters
+            .iter()
+            .position(|info| info.display == display)
+            .map(|index| Letter(index as u8 + 1))
+    }
+
+    // Splits a word into letters, always taking the longest tile that matches so "LLAMA" is
+    // LL-A-M-A in Spanish. Where two single letters also spell a digraph, like N and G in
+    // Welsh, only the digraph can be written. Words with anything not in the alphabet give None.
+    pub fn parse(&self, word: &str) -> Option<Vec<Letter>> {
+        let word = word.trim().to_uppercase();
+        if word.is_empty() {
+            return None;
+        }
+        let mut letters = vec![];
+        let mut rest = word.as_str();
+        while !rest.is_empty() {
+            let (index, info) = self
+                .letters
+                .iter()
+                .enumerate()
+                .filter(|(_, info)| !info.display.is_empty() && rest.starts_with(&info.display))
+                .max_by_key(|(_, info)| info.display.len())?;
+            letters.push(Letter(index as u8 + 1));
+            rest = &rest[info.display.len()..];
+        }
+        Some(letters)
+    }
+
+    pub fn spell(&self, letters: &[Letter]) -> String {
+        letters.iter().map(|letter| self.display(*letter)).collect()
+    }
+
+    // How a tile is named in messages, the blank is spelt out
+    pub fn describe(&self, letter: Letter) -> String {
+        if letter.is_blank() {
+            "blank".to_string()
+        } else {
+            self.display(letter).to_string()
+        }
+    }
+}
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Alphabet::english()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn welsh_digraphs_take_the_longest_match() {
+        let welsh = Alphabet::welsh();
+        let letters = welsh.parse("llongau").unwrap();
+        assert_eq!(welsh.spell(&letters), "LLONGAU");
+        let tiles: Vec<&str> = letters.iter().map(|l| welsh.display(*l)).collect();
+        assert_eq!(tiles, ["LL", "O", "NG", "A", "U"]);
+        assert_eq!(welsh.parse("rhwng").unwrap().len(), 3);
+        assert_eq!(welsh.parse("bangor").unwrap().len(), 5);
+        // K isn't a Welsh letter
+        assert_eq!(welsh.parse("kate"), None);
+    }
+
+    #[test]
+    fn welsh_bag_has_a_hundred_tiles() {
+        let welsh = Alphabet::by_name("Welsh").unwrap();
+        assert_eq!(welsh.bag().len(), 100);
+    }
+}
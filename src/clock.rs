@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+// Taken off a player's score for each minute, or part of one, they went over their time
+pub const OVERTIME_PENALTY: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeControl {
+    // What every clock starts with
+    pub initial: Duration,
+    // Added to a player's clock after each of their turns
+    pub increment: Duration,
+}
+
+impl TimeControl {
+    pub fn new(initial: Duration, increment: Duration) -> Self {
+        TimeControl { initial, increment }
+    }
+}
+
+// Counts down while it is the player's turn. Once it runs out the player can carry on, but the
+// time they take is kept as overtime and costs them points at the end of the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clock {
+    pub(crate) remaining: Duration,
+    pub(crate) overtime: Duration,
+}
+
+impl Clock {
+    pub fn new(time: Duration) -> Self {
+        Clock {
+            remaining: time,
+            overtime: Duration::ZERO,
+        }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.remaining
+    }
+
+    pub fn overtime(&self) -> Duration {
+        self.overtime
+    }
+
+    pub fn is_overtime(&self) -> bool {
+        self.remaining.is_zero() && !self.overtime.is_zero()
+    }
+
+    pub fn run(&mut self, elapsed: Duration) {
+        match self.remaining.checked_sub(elapsed) {
+            Some(remaining) => self.remaining = remaining,
+            None => {
+                self.overtime += elapsed - self.remaining;
+                self.remaining = Duration::ZERO;
+            }
+        }
+    }
+
+    // Increments stop once a player is in overtime, time already over can't be won back
+    pub fn add(&mut self, time: Duration) {
+        if !self.is_overtime() {
+            self.remaining += time;
+        }
+    }
+
+    pub fn penalty(&self) -> u32 {
+        let minutes = self.overtime.as_millis().div_ceil(60_000);
+        u32::try_from(minutes)
+            .unwrap_or(u32::MAX)
+            .saturating_mul(OVERTIME_PENALTY)
+    }
+}
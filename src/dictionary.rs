@@ -0,0 +1,194 @@
+use crate::alphabet::{Alphabet, Letter};
+use std::fs;
+use std::io::Result as IoResult;
+use std::path::Path;
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    terminal: bool,
+    // Kept sorted by letter so lookups can binary search
+    children: Vec<(Letter, u32)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Dictionary {
+    nodes: Vec<Node>,
+    words: usize,
+    // Spells the words looked up as text
+    alphabet: Alphabet,
+}
+
+impl Dictionary {
+    pub fn new(alphabet: &Alphabet) -> Self {
+        Dictionary {
+            nodes: vec![Node::default()],
+            words: 0,
+            alphabet: alphabet.clone(),
+        }
+    }
+
+    // Word lists are plain text with one word per line, blank lines and lines starting
+    // with '#' are skipped
+    pub fn from_file<P: AsRef<Path>>(path: P, alphabet: &Alphabet) -> IoResult<Self> {
+        let contents = fs::read_to_string(path)?;
+        Ok(Dictionary::from_words(
+            contents
+                .lines()
+                .filter(|line| !line.trim_start().starts_with('#')),
+            alphabet,
+        ))
+    }
+
+    // Words that can't be spelt with the alphabet's tiles are left out
+    pub fn from_words<I, S>(words: I, alphabet: &Alphabet) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut dictionary = Dictionary::new(alphabet);
+        for word in words {
+            if let Some(letters) = alphabet.parse(word.as_ref()) {
+                dictionary.insert(&letters);
+            }
+        }
+        dictionary
+    }
+
+    pub fn insert(&mut self, word: &[Letter]) -> bool {
+        if word.is_empty() || word.iter().any(|letter| letter.is_blank()) {
+            return false;
+        }
+        let mut node = 0;
+        for &letter in word {
+            node = match self.child(node, letter) {
+                Some(child) => child,
+                None => {
+                    let child = self.nodes.len() as u32;
+                    self.nodes.push(Node::default());
+                    let children = &mut self.nodes[node as usize].children;
+                    let index = children
+                        .binary_search_by_key(&letter, |(l, _)| *l)
+                        .unwrap_err();
+                    children.insert(index, (letter, child));
+                    child
+                }
+            };
+        }
+        let terminal = &mut self.nodes[node as usize].terminal;
+        if *terminal {
+            false
+        } else {
+            *terminal = true;
+            self.words += 1;
+            true
+        }
+    }
+
+    pub fn contains(&self, word: &[Letter]) -> bool {
+        if word.is_empty() {
+            return false;
+        }
+        let mut node = 0;
+        for &letter in word {
+            match self.child(node, letter) {
+                Some(child) => node = child,
+                None => return false,
+            }
+        }
+        self.is_terminal(node)
+    }
+
+    // Looks a word up by its spelling, anything the alphabet can't spell isn't a word
+    pub fn contains_word(&self, word: &str) -> bool {
+        self.alphabet
+            .parse(word)
+            .is_some_and(|letters| self.contains(&letters))
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    // A dictionary of every word's letters sorted into order, so a word can be looked up by
+    // any of its anagrams once its letters are sorted the same way
+    pub fn alphagrams(&self) -> Dictionary {
+        let mut alphagrams = Dictionary::new(&self.alphabet);
+        let mut stack = vec![(self.root(), vec![])];
+        while let Some((node, word)) = stack.pop() {
+            if self.is_terminal(node) {
+                let mut sorted: Vec<Letter> = word.clone();
+                sorted.sort();
+                alphagrams.insert(&sorted);
+            }
+            for (letter, child) in self.children(node) {
+                let mut longer = word.clone();
+                longer.push(*letter);
+                stack.push((*child, longer));
+            }
+        }
+        alphagrams
+    }
+
+    pub fn len(&self) -> usize {
+        self.words
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words == 0
+    }
+
+    pub(crate) fn root(&self) -> u32 {
+        0
+    }
+
+    pub(crate) fn child(&self, node: u32, letter: Letter) -> Option<u32> {
+        let children = &self.nodes[node as usize].children;
+        children
+            .binary_search_by_key(&letter, |(l, _)| *l)
+            .ok()
+            .map(|index| children[index].1)
+    }
+
+    pub(crate) fn children(&self, node: u32) -> &[(Letter, u32)] {
+        &self.nodes[node as usize].children
+    }
+
+    pub(crate) fn is_terminal(&self, node: u32) -> bool {
+        self.nodes[node as usize].terminal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_are_looked_up_by_spelling() {
+        let spanish = Alphabet::spanish();
+        let dictionary = Dictionary::from_words(["llama", "kiwi", "cama"], &spanish);
+        // K isn't a Spanish tile
+        assert_eq!(dictionary.len(), 2);
+        assert!(dictionary.contains_word("LLAMA"));
+        assert!(dictionary.contains_word(" cama "));
+        assert!(!dictionary.contains_word("lama"));
+        assert!(!dictionary.contains_word("kiwi"));
+        assert!(!dictionary.contains_word(""));
+    }
+
+    #[test]
+    fn word_lists_skip_comments() {
+        // With a '#' tile, comments would otherwise be words
+        let mut alphabet = Alphabet::english();
+        alphabet.add_letter("#", 1, 0);
+        let path = std::env::temp_dir().join(format!("worders-words-{}.txt", std::process::id()));
+        fs::write(&path, "#cat\ndog\n\n  #bat\nc#w\n").unwrap();
+        let dictionary = Dictionary::from_file(&path, &alphabet);
+        fs::remove_file(&path).unwrap();
+        let dictionary = dictionary.unwrap();
+        assert_eq!(dictionary.len(), 2);
+        assert!(dictionary.contains_word("dog"));
+        assert!(dictionary.contains_word("c#w"));
+        assert!(!dictionary.contains_word("#cat"));
+        assert!(!dictionary.contains_word("#bat"));
+    }
+}
@@ -1,106 +1,1976 @@
 #![allow(dead_code)]
 
+use crate::ai::{self, Difficulty};
+use crate::alphabet::{Alphabet, Letter};
+use crate::clock::{Clock, TimeControl};
+use crate::dictionary::Dictionary;
+use crate::history::{Move, MoveKind, TurnState};
+use crate::identity::{Identity, PlayerId};
+use crate::rng::SeededRng;
+use crate::rules::Rules;
 use rand::{thread_rng, Rng};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 
-const SPACE: char = 0x20 as char;
-const DEFAULT_BAG: [char; 100] = [
-    SPACE, SPACE, 'A', 'A', 'A', 'A', 'A', 'A', 'A', 'A', 'A', 'B', 'B', 'C', 'C', 'D', 'D', 'D',
-    'D', 'E', 'E', 'E', 'E', 'E', 'E', 'E', 'E', 'E', 'E', 'E', 'E', 'F', 'F', 'G', 'G', 'G', 'H',
-    'H', 'I', 'I', 'I', 'I', 'I', 'I', 'I', 'I', 'I', 'J', 'K', 'L', 'L', 'L', 'L', 'M', 'M', 'N',
-    'N', 'N', 'N', 'N', 'N', 'O', 'O', 'O', 'O', 'O', 'O', 'O', 'O', 'P', 'P', 'Q', 'R', 'R', 'R',
-    'R', 'R', 'R', 'S', 'S', 'S', 'S', 'T', 'T', 'T', 'T', 'T', 'T', 'U', 'U', 'U', 'U', 'V', 'V',
-    'W', 'W', 'X', 'Y', 'Y', 'Z',
+pub const MAX_SCORELESS_TURNS: u8 = 6;
+// Team games are always two teams, partners sit in every other seat so they take turns with
+// the other team
+pub const TEAMS: usize = 2;
+const COMPUTER_NAMES: [&str; 8] = [
+    "Computer 1",
+    "Computer 2",
+    "Computer 3",
+    "Computer 4",
+    "Computer 5",
+    "Computer 6",
+    "Computer 7",
+    "Computer 8",
 ];
 
 #[derive(Debug, Clone)]
 pub struct GameState {
-    id: usize,
-    bag: Vec<char>,
-    placed: Vec<char>,
-    turn: u8,
-    players: [Option<Player>; 4],
+    pub(crate) id: usize,
+    pub(crate) seed: u64,
+    pub(crate) rules: Rules,
+    pub(crate) bag: Bag,
+    pub(crate) board: Board,
+    pub(crate) dictionary: Option<Arc<Dictionary>>,
+    pub(crate) variant: Variant,
+    // The dictionary's words with their letters sorted, only made for Clabbers
+    pub(crate) alphagrams: Option<Arc<Dictionary>>,
+    pub(crate) common_words: Option<Arc<Dictionary>>,
+    pub(crate) challenge_rule: ChallengeRule,
+    pub(crate) pending: Option<PendingPlay>,
+    // Seats that lost a double challenge and miss their next turn
+    pub(crate) forfeits: Vec<bool>,
+    // Untimed games have no clocks
+    pub(crate) time_control: Option<TimeControl>,
+    pub(crate) clocks: Vec<Clock>,
+    pub(crate) team_play: bool,
+    pub(crate) turn: u8,
+    pub(crate) scoreless_turns: u8,
+    pub(crate) standings: Option<Standings>,
+    pub(crate) players: Vec<Option<Player>>,
+    pub(crate) history: Vec<Move>,
 }
 
 impl GameState {
-    pub fn new(id: usize) -> Self {
+    // Games created with the same seed draw tiles in the same order, without one a random
+    // seed is picked
+    pub fn new(id: usize, rules: Rules, seed: Option<u64>) -> Self {
+        let seed = seed.unwrap_or_else(|| thread_rng().gen());
         GameState {
             id,
-            bag: DEFAULT_BAG.to_vec(),
-            placed: vec![],
+            seed,
+            bag: Bag::new(&rules.alphabet.bag(), seed),
+            board: Board::from_layout(&rules.layout),
+            dictionary: None,
+            variant: Variant::Standard,
+            alphagrams: None,
+            common_words: None,
+            challenge_rule: ChallengeRule::Void,
+            pending: None,
+            forfeits: vec![false; rules.players],
+            time_control: None,
+            clocks: vec![],
+            team_play: false,
             turn: 0,
-            players: [None, None, None, None],
+            scoreless_turns: 0,
+            standings: None,
+            players: vec![None; rules.players],
+            history: vec![],
+            rules,
         }
     }
 
     fn initialize(&mut self) {}
 
-    pub fn add_player(&mut self, player: Player) -> bool {
-        let mut empty_slots = self
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    // Games without a dictionary accept any word
+    pub fn set_dictionary(&mut self, dictionary: Arc<Dictionary>) {
+        self.dictionary = Some(dictionary);
+        self.index_alphagrams();
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    pub fn set_variant(&mut self, variant: Variant) {
+        self.variant = variant;
+        self.index_alphagrams();
+    }
+
+    fn index_alphagrams(&mut self) {
+        self.alphagrams = match (self.variant, &self.dictionary) {
+            (Variant::Clabbers, Some(dictionary)) => Some(Arc::new(dictionary.alphagrams())),
+            _ => None,
+        };
+    }
+
+    // Whether the dictionary allows the word, in Clabbers any order of a listed word's letters
+    // is allowed. Games without a dictionary allow anything.
+    pub fn allows_word(&self, letters: &[Letter]) -> bool {
+        match (&self.alphagrams, &self.dictionary) {
+            (Some(alphagrams), _) => {
+                let mut sorted = letters.to_vec();
+                sorted.sort();
+                alphagrams.contains(&sorted)
+            }
+            (None, Some(dictionary)) => dictionary.contains(letters),
+            (None, None) => true,
+        }
+    }
+
+    pub fn dictionary(&self) -> Option<&Dictionary> {
+        self.dictionary.as_deref()
+    }
+
+    // The smaller vocabulary that beginner computer players stick to
+    pub fn set_common_words(&mut self, common_words: Arc<Dictionary>) {
+        self.common_words = Some(common_words);
+    }
+
+    pub fn common_words(&self) -> Option<&Dictionary> {
+        self.common_words.as_deref()
+    }
+
+    pub fn challenge_rule(&self) -> ChallengeRule {
+        self.challenge_rule
+    }
+
+    pub fn set_challenge_rule(&mut self, rule: ChallengeRule) {
+        self.challenge_rule = rule;
+    }
+
+    pub fn time_control(&self) -> Option<TimeControl> {
+        self.time_control
+    }
+
+    // Every seat's clock is reset to the starting time, or taken away if `None`
+    pub fn set_time_control(&mut self, time_control: Option<TimeControl>) {
+        self.time_control = time_control;
+        self.clocks = match time_control {
+            Some(time_control) => vec![Clock::new(time_control.initial); self.players.len()],
+            None => vec![],
+        };
+    }
+
+    pub fn clock(&self, player: usize) -> Option<&Clock> {
+        self.clocks.get(player)
+    }
+
+    // The game doesn't read the time itself, whoever runs it says how long has passed since
+    // they last did. It is charged to the player whose turn it is.
+    pub fn run_clock(&mut self, elapsed: Duration) {
+        let current = self.current_player();
+        if self.is_over() || self.player(current).is_none() {
+            return;
+        }
+        if let Some(clock) = self.clocks.get_mut(current) {
+            clock.run(elapsed);
+        }
+    }
+
+    pub fn team_play(&self) -> bool {
+        self.team_play
+    }
+
+    // Teams need the seats to split evenly between them, returns whether team play was set
+    pub fn set_team_play(&mut self, team_play: bool) -> bool {
+        if team_play && !self.players.len().is_multiple_of(TEAMS) {
+            return false;
+        }
+        self.team_play = team_play;
+        true
+    }
+
+    pub fn team_of(&self, player: usize) -> Option<usize> {
+        if self.team_play {
+            Some(player % TEAMS)
+        } else {
+            None
+        }
+    }
+
+    // Without teams everyone is only on their own side
+    pub fn same_team(&self, player: usize, other: usize) -> bool {
+        player == other
+            || self
+                .team_of(player)
+                .is_some_and(|t| self.team_of(other) == Some(t))
+    }
+
+    // The other seated players on the player's team
+    pub fn partners(&self, player: usize) -> Vec<usize> {
+        (0..self.players.len())
+            .filter(|other| *other != player && self.same_team(player, *other))
+            .filter(|other| self.players[*other].is_some())
+            .collect()
+    }
+
+    // Partners share a score, made up of what each of them has scored
+    pub fn team_score(&self, team: usize) -> i32 {
+        (0..self.players.len())
+            .filter(|seat| self.team_of(*seat) == Some(team))
+            .filter_map(|seat| self.player(seat))
+            .map(|p| p.score)
+            .sum()
+    }
+
+    // Seats the player in the first empty slot and deals them a full hand
+    // People can only sit at a table once, computer players can take any number of seats
+    pub fn add_player(&mut self, player: Player) -> Result<usize, JoinError> {
+        self.take_seat(player, None)
+    }
+
+    // Like add_player but only the given team's seats are offered, in games without teams any
+    // seat will do
+    pub fn add_player_to_team(&mut self, player: Player, team: usize) -> Result<usize, JoinError> {
+        if !self.team_play {
+            return self.add_player(player);
+        }
+        if team >= TEAMS {
+            return Err(JoinError::NoSuchTeam(team));
+        }
+        self.take_seat(player, Some(team))
+    }
+
+    fn take_seat(&mut self, player: Player, team: Option<usize>) -> Result<usize, JoinError> {
+        if !player.is_computer() {
+            if let Some(seat) = self.seat_of(player.id()) {
+                return Err(JoinError::AlreadySeated(seat));
+            }
+        }
+        let seat = (0..self.players.len())
+            .filter(|seat| team.is_none() || self.team_of(*seat) == team)
+            .find(|seat| self.players[*seat].is_none())
+            .ok_or(match team {
+                Some(team) => JoinError::TeamFull(team),
+                None => JoinError::TableFull,
+            })?;
+        self.players[seat] = Some(player);
+        self.fill_hand(seat);
+        if self.players[self.turn as usize].is_none() {
+            self.turn = seat as u8;
+        }
+        Ok(seat)
+    }
+
+    pub fn add_computer(&mut self, difficulty: Difficulty) -> Option<usize> {
+        let seat = self.players.iter().position(|p| p.is_none())?;
+        let name = COMPUTER_NAMES.get(seat).unwrap_or(&"Computer");
+        let identity = Identity::new(PlayerId::computer(difficulty), name)
+            .expect("Computer names should be valid");
+        self.add_player(Player::computer(identity, difficulty)).ok()
+    }
+
+    // Gives every empty seat to a computer player
+    pub fn fill_with_computers(&mut self, difficulty: Difficulty) -> Vec<usize> {
+        let mut seats = vec![];
+        while let Some(seat) = self.add_computer(difficulty) {
+            seats.push(seat);
+        }
+        seats
+    }
+
+    // The seat a person is sitting in
+    pub fn seat_of(&self, id: PlayerId) -> Option<usize> {
+        self.players
+            .iter()
+            .position(|p| p.as_ref().is_some_and(|p| !p.is_computer() && p.id() == id))
+    }
+
+    pub fn player(&self, player: usize) -> Option<&Player> {
+        self.players.get(player).and_then(|p| p.as_ref())
+    }
+
+    pub fn seats(&self) -> usize {
+        self.players.len()
+    }
+
+    pub fn current_player(&self) -> usize {
+        self.turn as usize
+    }
+
+    pub fn play(&mut self, player: usize, placements: &[Placement]) -> Result<Score, MoveError> {
+        self.check_turn(player)?;
+        self.validate_move(player, placements)?;
+        let state = self.snapshot();
+        self.close_challenge()?;
+        if self.is_over() {
+            return Err(MoveError::GameOver);
+        }
+        let score = self.score_move(placements);
+        let (rack, score_before) = self.rack_and_score(player);
+        if let Some(current) = self.players[player].as_mut() {
+            for placement in placements {
+                current.take_from_hand(placement.tile.used());
+            }
+            current.score += score.total as i32;
+        }
+        for placement in placements {
+            self.board.place(placement.x, placement.y, placement.tile);
+        }
+        self.fill_hand(player);
+        if score.total == 0 {
+            self.scoreless_turns += 1;
+        } else {
+            self.scoreless_turns = 0;
+        }
+        self.record(
+            player,
+            rack,
+            score_before,
+            MoveKind::Play {
+                placements: placements.to_vec(),
+                score: score.clone(),
+            },
+            state,
+        );
+        let went_out = self.players[player]
+            .as_ref()
+            .is_some_and(|p| p.hand.is_empty())
+            && self.bag.is_empty();
+        if self.challenge_rule != ChallengeRule::Void {
+            // The play can still be challenged, so going out only ends the game once it is
+            // accepted
+            self.pending = Some(PendingPlay {
+                player,
+                placements: placements.to_vec(),
+                score: score.clone(),
+                went_out,
+            });
+            if !went_out {
+                self.end_turn();
+            }
+        } else if went_out {
+            self.finish(EndReason::WentOut(player));
+        } else {
+            self.end_turn();
+        }
+        Ok(score)
+    }
+
+    pub fn act(&mut self, player: usize, action: &Action) -> Result<(), MoveError> {
+        match action {
+            Action::Play(placements) => self.play(player, placements).map(|_| ()),
+            Action::Pass => self.pass(player),
+            Action::Exchange(tiles) => self.exchange(player, tiles),
+        }
+    }
+
+    // Takes the turn for a computer player if it is one's turn, returning what it did
+    pub fn play_computer_turn(&mut self) -> Option<Action> {
+        let seat = self.current_player();
+        if self.is_over() {
+            return None;
+        }
+        let difficulty = self.player(seat)?.difficulty()?;
+        let action = ai::choose_action(self, seat, difficulty);
+        match self.act(seat, &action) {
+            Ok(()) => Some(action),
+            Err(MoveError::GameOver) => None,
+            Err(_) => self.pass(seat).ok().map(|_| Action::Pass),
+        }
+    }
+
+    pub fn pending_play(&self) -> Option<&PendingPlay> {
+        self.pending.as_ref()
+    }
+
+    // Closes the challenge window on the last play, this happens automatically when the next
+    // player takes their turn. Only the other players can accept a play, and accepting one that
+    // went out ends the game.
+    pub fn accept_play(&mut self, player: usize) -> Result<(), MoveError> {
+        if self.player(player).is_none() {
+            return Err(MoveError::NoSuchPlayer(player));
+        }
+        if self
+            .pending
+            .as_ref()
+            .is_some_and(|pending| pending.player == player)
+        {
+            return Err(MoveError::OwnPlay);
+        }
+        self.close_challenge()
+    }
+
+    pub(crate) fn close_challenge(&mut self) -> Result<(), MoveError> {
+        if self.is_over() {
+            return Err(MoveError::GameOver);
+        }
+        if let Some(pending) = self.pending.take() {
+            if pending.went_out {
+                self.finish(EndReason::WentOut(pending.player));
+            }
+        }
+        Ok(())
+    }
+
+    // Checks every word made by the last play against the dictionary, a phony play is taken
+    // back off the board and under the double rule a failed challenger loses their next turn
+    pub fn challenge(&mut self, challenger: usize) -> Result<ChallengeOutcome, MoveError> {
+        if self.is_over() {
+            return Err(MoveError::GameOver);
+        }
+        if self.player(challenger).is_none() {
+            return Err(MoveError::NoSuchPlayer(challenger));
+        }
+        let state = self.snapshot();
+        let pending = match &self.pending {
+            Some(pending) if pending.player != challenger => pending.clone(),
+            Some(_) => return Err(MoveError::OwnPlay),
+            None => return Err(MoveError::NothingToChallenge),
+        };
+        if self.dictionary.is_none() {
+            return Err(MoveError::NoDictionary);
+        }
+        let phonies: Vec<String> = pending
+            .score
+            .words
+            .iter()
+            .filter(|word| !self.allows_word(&word.letters))
+            .map(|word| word.word.clone())
+            .collect();
+
+        if !phonies.is_empty() {
+            self.remove_phony(challenger, phonies.clone())?;
+            return Ok(ChallengeOutcome::Phony { words: phonies });
+        }
+
+        self.pending = None;
+        let forfeit = self.challenge_rule == ChallengeRule::Double;
+        let (rack, score_before) = self.rack_and_score(challenger);
+        self.record(
+            challenger,
+            rack,
+            score_before,
+            MoveKind::ChallengeFailed {
+                challenged: pending.player,
+                forfeit,
+            },
+            state,
+        );
+        if pending.went_out {
+            self.finish(EndReason::WentOut(pending.player));
+        } else if forfeit {
+            if self.current_player() == challenger {
+                self.lose_turn(challenger);
+                self.end_turn();
+            } else {
+                self.forfeits[challenger] = true;
+            }
+        }
+        Ok(ChallengeOutcome::Valid { forfeit })
+    }
+
+    // Takes the pending play back off the board after a successful challenge of `words`
+    pub(crate) fn remove_phony(
+        &mut self,
+        challenger: usize,
+        words: Vec<String>,
+    ) -> Result<(), MoveError> {
+        let state = self.snapshot();
+        let pending = match &self.pending {
+            Some(pending) if pending.player != challenger => pending.clone(),
+            Some(_) => return Err(MoveError::OwnPlay),
+            None => return Err(MoveError::NothingToChallenge),
+        };
+        // Undoing the play puts the tiles, bag and turn back how they were before it, the play
+        // itself stays in the history followed by its removal
+        let (rack, score_before) = self.rack_and_score(pending.player);
+        let undone = self.undo_last();
+        self.history.extend(undone.into_iter().rev().take(1));
+        self.pending = None;
+        self.scoreless_turns += 1;
+        self.record(
+            pending.player,
+            rack,
+            score_before,
+            MoveKind::PhonyRemoved {
+                challenger,
+                placements: pending.placements,
+                words,
+            },
+            state,
+        );
+        self.end_turn();
+        Ok(())
+    }
+
+    pub fn pass(&mut self, player: usize) -> Result<(), MoveError> {
+        self.check_turn(player)?;
+        let state = self.snapshot();
+        self.close_challenge()?;
+        // Passing after going out only accepts the play, which ends the game
+        if self.is_over() {
+            return Ok(());
+        }
+        self.scoreless_turns += 1;
+        let (rack, score_before) = self.rack_and_score(player);
+        self.record(player, rack, score_before, MoveKind::Pass, state);
+        self.end_turn();
+        Ok(())
+    }
+
+    // Swaps the given tiles for new ones from the bag, only allowed while the bag still has a
+    // full hand's worth of tiles in it
+    pub fn exchange(&mut self, player: usize, tiles: &[Letter]) -> Result<(), MoveError> {
+        self.check_turn(player)?;
+        if tiles.is_empty() {
+            return Err(MoveError::NoTiles);
+        }
+        if self.bag.len() < self.rules.rack_size {
+            return Err(MoveError::BagTooSmall(self.bag.len()));
+        }
+        let current = self.players[player]
+            .as_mut()
+            .ok_or(MoveError::NoSuchPlayer(player))?;
+        let mut hand = current.hand.clone();
+        for tile in tiles {
+            match hand.iter().position(|c| c == tile) {
+                Some(index) => {
+                    hand.swap_remove(index);
+                }
+                None => return Err(MoveError::NotInHand(self.rules.alphabet.describe(*tile))),
+            }
+        }
+        let state = self.snapshot();
+        self.close_challenge()?;
+        if self.is_over() {
+            return Err(MoveError::GameOver);
+        }
+        let (rack, score_before) = self.rack_and_score(player);
+        if let Some(current) = self.players[player].as_mut() {
+            for tile in tiles {
+                current.take_from_hand(*tile);
+            }
+        }
+        self.fill_hand(player);
+        self.bag.return_tiles(tiles);
+        self.scoreless_turns += 1;
+        self.record(
+            player,
+            rack,
+            score_before,
+            MoveKind::Exchange {
+                tiles: tiles.to_vec(),
+            },
+            state,
+        );
+        self.end_turn();
+        Ok(())
+    }
+
+    // Every move made so far in the order they happened
+    pub fn history(&self) -> &[Move] {
+        &self.history
+    }
+
+    // Takes back the last move along with anything that happened automatically because of it,
+    // like a skipped turn or the end of the game. The moves undone are returned most recent
+    // first. Clocks aren't wound back, the time spent on a move stays spent.
+    pub fn undo_last(&mut self) -> Vec<Move> {
+        let mut undone = vec![];
+        while let Some(last) = self.history.pop() {
+            self.restore(&last);
+            let automatic = last.kind.is_automatic();
+            undone.push(last);
+            if !automatic {
+                break;
+            }
+        }
+        undone
+    }
+
+    pub(crate) fn snapshot(&self) -> TurnState {
+        TurnState {
+            bag: self.bag.clone(),
+            turn: self.turn,
+            scoreless_turns: self.scoreless_turns,
+            pending: self.pending.clone(),
+            forfeits: self.forfeits.clone(),
+            standings: self.standings.clone(),
+        }
+    }
+
+    fn restore(&mut self, undone: &Move) {
+        match &undone.kind {
+            MoveKind::Play { placements, .. } => {
+                for placement in placements {
+                    self.board.remove(placement.x, placement.y);
+                }
+            }
+            MoveKind::PhonyRemoved { placements, .. } => {
+                for placement in placements {
+                    self.board.place(placement.x, placement.y, placement.tile);
+                }
+            }
+            _ => {}
+        }
+        if let Some(player) = self.players[undone.player].as_mut() {
+            player.hand = undone.rack.clone();
+            player.score = undone.score_before;
+        }
+        let state = undone.state.clone();
+        self.bag = state.bag;
+        self.turn = state.turn;
+        self.scoreless_turns = state.scoreless_turns;
+        self.pending = state.pending;
+        self.forfeits = state.forfeits;
+        self.standings = state.standings;
+    }
+
+    fn rack_and_score(&self, player: usize) -> (Vec<Letter>, i32) {
+        self.player(player)
+            .map_or((vec![], 0), |p| (p.hand.clone(), p.score))
+    }
+
+    // Logs a move that has already been made, `state` is how the game was before it
+    fn record(
+        &mut self,
+        player: usize,
+        rack: Vec<Letter>,
+        score_before: i32,
+        kind: MoveKind,
+        state: TurnState,
+    ) {
+        let score_after = self.player(player).map_or(0, |p| p.score);
+        self.history.push(Move {
+            player,
+            rack,
+            kind,
+            score_before,
+            score_after,
+            state,
+        });
+    }
+
+    fn lose_turn(&mut self, player: usize) {
+        let state = self.snapshot();
+        let (rack, score) = self.rack_and_score(player);
+        self.scoreless_turns += 1;
+        self.record(player, rack, score, MoveKind::LostTurn, state);
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.standings.is_some()
+    }
+
+    pub fn standings(&self) -> Option<&Standings> {
+        self.standings.as_ref()
+    }
+
+    fn end_turn(&mut self) {
+        // A removed phony already earned its increment when it was played, and a lost turn
+        // wasn't played at all
+        let played = !matches!(
+            self.history.last().map(|entry| &entry.kind),
+            Some(MoveKind::PhonyRemoved { .. } | MoveKind::LostTurn)
+        );
+        if let (true, Some(time_control), Some(clock)) = (
+            played,
+            self.time_control,
+            self.clocks.get_mut(self.turn as usize),
+        ) {
+            clock.add(time_control.increment);
+        }
+        if self.scoreless_turns >= MAX_SCORELESS_TURNS {
+            self.finish(EndReason::Scoreless);
+        } else {
+            self.advance_turn();
+        }
+    }
+
+    // Everyone loses the value of the tiles left in their hand, and a player who went out
+    // gains the total of everyone else's
+    fn finish(&mut self, reason: EndReason) {
+        let state = self.snapshot();
+        let remaining: Vec<u32> = self
             .players
-            .iter_mut()
-            .enumerate()
-            .filter(|(_, s)| s.is_none());
-        if let Some((first_slot, _)) = empty_slots.next() {
-            self.players[first_slot] = Some(player);
-            true
+            .iter()
+            .map(|p| p.as_ref().map_or(0, |p| p.hand_value(&self.rules)))
+            .collect();
+        // Whoever went out is given the tiles left with the other side, their partners' tiles
+        // are only lost
+        let adjustments: Vec<i32> = (0..self.players.len())
+            .map(|seat| match reason {
+                EndReason::WentOut(out) if out == seat => (0..self.players.len())
+                    .filter(|other| !self.same_team(seat, *other))
+                    .map(|other| remaining[other])
+                    .sum::<u32>() as i32,
+                _ => -(remaining[seat] as i32),
+            })
+            .collect();
+        let teams: Vec<Option<usize>> = (0..self.players.len())
+            .map(|seat| self.team_of(seat))
+            .collect();
+        let mut standings: Vec<Standing> = vec![];
+        let mut end_racks = vec![];
+        for (seat, player) in self.players.iter_mut().enumerate() {
+            if let Some(player) = player {
+                let adjustment = adjustments[seat];
+                let score_before = player.score;
+                player.score += adjustment;
+                end_racks.push(Move {
+                    player: seat,
+                    rack: player.hand.clone(),
+                    kind: MoveKind::EndRack { adjustment },
+                    score_before,
+                    score_after: player.score,
+                    state: state.clone(),
+                });
+                standings.push(Standing {
+                    seat,
+                    username: player.username().to_string(),
+                    score: player.score,
+                    adjustment,
+                    time_penalty: 0,
+                    team: teams[seat],
+                    rank: 0,
+                });
+            }
+        }
+        let mut standings = Standings { reason, standings };
+        standings.rank();
+        self.standings = Some(standings);
+        self.history.extend(end_racks);
+        for seat in 0..self.clocks.len() {
+            let penalty = self.clocks[seat].penalty();
+            if penalty > 0 && self.players[seat].is_some() {
+                self.penalise_time(seat, penalty);
+            }
+        }
+    }
+
+    // Takes points off a player who went over their time once the game is over
+    pub(crate) fn penalise_time(&mut self, player: usize, points: u32) {
+        let state = self.snapshot();
+        let (rack, score_before) = self.rack_and_score(player);
+        let score = match self.players[player].as_mut() {
+            Some(current) => {
+                current.score -= points as i32;
+                current.score
+            }
+            None => return,
+        };
+        self.record(
+            player,
+            rack,
+            score_before,
+            MoveKind::TimePenalty { points },
+            state,
+        );
+        if let Some(standings) = self.standings.as_mut() {
+            for standing in standings.standings.iter_mut() {
+                if standing.seat == player {
+                    standing.score = score;
+                    standing.time_penalty += points;
+                }
+            }
+            standings.rank();
+        }
+    }
+
+    fn check_turn(&self, player: usize) -> Result<(), MoveError> {
+        if self.is_over() {
+            Err(MoveError::GameOver)
+        } else if self.player(player).is_none() {
+            Err(MoveError::NoSuchPlayer(player))
+        } else if self.current_player() != player {
+            Err(MoveError::NotYourTurn(player))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn advance_turn(&mut self) {
+        let seats = self.players.len();
+        let current = self.turn as usize;
+        if let Some(next) = (1..=seats)
+            .map(|offset| (current + offset) % seats)
+            .find(|seat| self.players[*seat].is_some())
+        {
+            self.turn = next as u8;
+            // A forfeited turn is skipped as if the player had passed
+            if self.forfeits[next] {
+                self.forfeits[next] = false;
+                self.lose_turn(next);
+                self.end_turn();
+            }
+        }
+    }
+
+    fn fill_hand(&mut self, player: usize) {
+        while self.players[player]
+            .as_ref()
+            .is_some_and(|p| p.hand.len() < self.rules.rack_size)
+        {
+            match self.draw() {
+                Some(tile) => {
+                    if let Some(current) = self.players[player].as_mut() {
+                        current.insert_into_hand(tile);
+                    }
+                }
+                None => break,
+            }
+        }
+    }
+
+    pub fn validate_move(&self, player: usize, placements: &[Placement]) -> Result<(), MoveError> {
+        let player = self.player(player).ok_or(MoveError::NoSuchPlayer(player))?;
+        if placements.is_empty() {
+            return Err(MoveError::NoTiles);
+        }
+        for (i, placement) in placements.iter().enumerate() {
+            let (x, y) = (placement.x, placement.y);
+            if !self.rules.alphabet.contains(placement.tile.letter) {
+                return Err(if placement.tile.blank {
+                    MoveError::UndesignatedBlank { x, y }
+                } else {
+                    MoveError::NotALetter(placement.tile.letter)
+                });
+            }
+            if !self.board.in_bounds(x, y) {
+                return Err(MoveError::OutOfBounds { x, y });
+            }
+            if self.board.is_occupied(x, y) {
+                return Err(MoveError::Occupied { x, y });
+            }
+            if placements[..i].iter().any(|p| p.x == x && p.y == y) {
+                return Err(MoveError::DuplicateSquare { x, y });
+            }
+        }
+
+        let direction = placement_direction(placements).ok_or(MoveError::NotInLine)?;
+        let (first, last) = placement_span(placements, direction);
+        let mut touches_existing = false;
+        for position in first..=last {
+            let (x, y) = direction.at(placements[0].x, placements[0].y, position);
+            if self.board.is_occupied(x, y) {
+                touches_existing = true;
+            } else if !placements.iter().any(|p| p.x == x && p.y == y) {
+                return Err(MoveError::Gap { x, y });
+            }
+        }
+
+        if self.board.is_empty() {
+            let (centre_x, centre_y) = self.board.centre();
+            if !placements
+                .iter()
+                .any(|p| p.x == centre_x && p.y == centre_y)
+            {
+                return Err(MoveError::MissesCentre);
+            }
+            if placements.len() < 2 {
+                return Err(MoveError::OpeningTooShort);
+            }
+        } else {
+            touches_existing = touches_existing
+                || placements
+                    .iter()
+                    .any(|p| self.board.has_neighbour(p.x, p.y));
+            if !touches_existing {
+                return Err(MoveError::NotConnected);
+            }
+        }
+
+        let mut hand = player.hand.clone();
+        for placement in placements {
+            let wanted = placement.tile.used();
+            match hand.iter().position(|c| *c == wanted) {
+                Some(index) => {
+                    hand.swap_remove(index);
+                }
+                None => return Err(MoveError::NotInHand(self.rules.alphabet.describe(wanted))),
+            }
+        }
+
+        // Under the other challenge rules phony words are allowed until they are challenged
+        if self.dictionary.is_some() && self.challenge_rule == ChallengeRule::Void {
+            for word in self.board.words_formed(placements) {
+                if !self.allows_word(&word.letters()) {
+                    return Err(MoveError::InvalidWord(word.text(&self.rules.alphabet)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn score_move(&self, placements: &[Placement]) -> Score {
+        calculate_score_from_placement(&self.board, &self.rules, placements)
+    }
+
+    fn draw(&mut self) -> Option<Letter> {
+        self.bag.draw()
+    }
+
+    pub fn bag(&self) -> &Bag {
+        &self.bag
+    }
+
+    // Checks that every tile in the starting distribution is in exactly one place, either the
+    // bag, a player's hand or the board
+    pub fn audit(&self) -> Result<(), TileAuditError> {
+        let mut counts: HashMap<Letter, i32> = HashMap::new();
+        for tile in self.rules.alphabet.bag() {
+            *counts.entry(tile).or_insert(0) += 1;
+        }
+        let on_board = self.board.tiles().map(|(_, _, tile)| tile.used());
+        let in_hands = self.players.iter().flatten().flat_map(|p| p.hand.iter());
+        for tile in self
+            .bag
+            .tiles()
+            .iter()
+            .chain(in_hands)
+            .copied()
+            .chain(on_board)
+        {
+            *counts.entry(tile).or_insert(0) -= 1;
+        }
+        let mut mismatches: Vec<(Letter, i32)> = counts
+            .into_iter()
+            .filter(|(_, count)| *count != 0)
+            .collect();
+        mismatches.sort();
+        match mismatches.first() {
+            Some((tile, missing)) => {
+                let expected = self.rules.alphabet.count(*tile) as i32;
+                Err(TileAuditError {
+                    tile: self.rules.alphabet.describe(*tile),
+                    expected: expected as usize,
+                    found: (expected - missing) as usize,
+                })
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bag {
+    pub(crate) tiles: Vec<Letter>,
+    pub(crate) rng: SeededRng,
+}
+
+impl Bag {
+    pub fn new(tiles: &[Letter], seed: u64) -> Self {
+        Bag {
+            tiles: tiles.to_vec(),
+            rng: SeededRng::new(seed),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    pub fn tiles(&self) -> &[Letter] {
+        &self.tiles
+    }
+
+    // Removes a random tile from the bag
+    pub fn draw(&mut self) -> Option<Letter> {
+        if self.tiles.is_empty() {
+            return None;
+        }
+        let index: usize = self.rng.gen_range(0..self.tiles.len());
+        Some(self.tiles.swap_remove(index))
+    }
+
+    // Orders the bag so the next tiles drawn are `draws`, stopping at the first one that isn't
+    // in it
+    pub(crate) fn arrange(&mut self, draws: &[Letter]) {
+        let mut rng = self.rng.clone();
+        // Where each tile still in the bag started, following the swaps `draw` will make
+        let mut remaining: Vec<usize> = (0..self.tiles.len()).collect();
+        for letter in draws {
+            let found = match remaining.iter().position(|i| self.tiles[*i] == *letter) {
+                Some(found) => found,
+                None => return,
+            };
+            let index: usize = rng.gen_range(0..remaining.len());
+            self.tiles.swap(remaining[index], remaining[found]);
+            remaining.swap_remove(index);
+        }
+    }
+
+    pub fn return_tiles(&mut self, tiles: &[Letter]) {
+        self.tiles.extend_from_slice(tiles);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileAuditError {
+    pub tile: String,
+    pub expected: usize,
+    pub found: usize,
+}
+
+impl fmt::Display for TileAuditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Expected {} {} tiles in the game but found {}",
+            self.expected, self.tile, self.found
+        )
+    }
+}
+
+impl std::error::Error for TileAuditError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Premium {
+    None,
+    DoubleLetter,
+    TripleLetter,
+    QuadrupleLetter,
+    DoubleWord,
+    TripleWord,
+    QuadrupleWord,
+}
+
+impl Premium {
+    pub(crate) fn from_layout(symbol: char) -> Option<Self> {
+        match symbol {
+            '.' => Some(Premium::None),
+            'd' => Some(Premium::DoubleLetter),
+            't' => Some(Premium::TripleLetter),
+            'q' => Some(Premium::QuadrupleLetter),
+            'D' | '*' => Some(Premium::DoubleWord),
+            'T' => Some(Premium::TripleWord),
+            'Q' => Some(Premium::QuadrupleWord),
+            _ => None,
+        }
+    }
+
+    pub fn letter_multiplier(&self) -> u32 {
+        match self {
+            Premium::DoubleLetter => 2,
+            Premium::TripleLetter => 3,
+            Premium::QuadrupleLetter => 4,
+            _ => 1,
+        }
+    }
+
+    pub fn word_multiplier(&self) -> u32 {
+        match self {
+            Premium::DoubleWord => 2,
+            Premium::TripleWord => 3,
+            Premium::QuadrupleWord => 4,
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Tile {
+    pub letter: Letter,
+    // Blanks show a letter on the board but are never worth any points
+    pub blank: bool,
+}
+
+impl Tile {
+    pub fn new(letter: Letter) -> Self {
+        Tile {
+            letter,
+            blank: false,
+        }
+    }
+
+    pub fn blank(letter: Letter) -> Self {
+        Tile {
+            letter,
+            blank: true,
+        }
+    }
+
+    // The tile as it was in the hand, before a blank was given its letter
+    pub fn used(&self) -> Letter {
+        if self.blank {
+            Letter::BLANK
         } else {
-            false
+            self.letter
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Placement {
+    pub x: u32,
+    pub y: u32,
+    pub tile: Tile,
+}
+
+impl Placement {
+    pub fn new(x: u32, y: u32, tile: Tile) -> Self {
+        Placement { x, y, tile }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Across,
+    Down,
+}
+
+impl Direction {
+    // Position of the square `offset` along this direction's axis, keeping the other axis fixed
+    pub(crate) fn at(&self, x: u32, y: u32, offset: u32) -> (u32, u32) {
+        match self {
+            Direction::Across => (offset, y),
+            Direction::Down => (x, offset),
+        }
+    }
+
+    pub(crate) fn offset(&self, x: u32, y: u32) -> u32 {
+        match self {
+            Direction::Across => x,
+            Direction::Down => y,
+        }
+    }
+
+    pub fn perpendicular(&self) -> Direction {
+        match self {
+            Direction::Across => Direction::Down,
+            Direction::Down => Direction::Across,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Word {
+    pub x: u32,
+    pub y: u32,
+    pub direction: Direction,
+    // Every tile in the word in reading order, flagged true if it was placed by this move
+    pub tiles: Vec<(u32, u32, Tile, bool)>,
+}
+
+impl Word {
+    pub fn letters(&self) -> Vec<Letter> {
+        self.tiles
+            .iter()
+            .map(|(_, _, tile, _)| tile.letter)
+            .collect()
+    }
+
+    pub fn text(&self, alphabet: &Alphabet) -> String {
+        alphabet.spell(&self.letters())
+    }
+}
+
+fn placement_direction(placements: &[Placement]) -> Option<Direction> {
+    let first = placements.first()?;
+    if placements.iter().all(|p| p.y == first.y) {
+        Some(Direction::Across)
+    } else if placements.iter().all(|p| p.x == first.x) {
+        Some(Direction::Down)
+    } else {
+        None
+    }
+}
+
+fn placement_span(placements: &[Placement], direction: Direction) -> (u32, u32) {
+    let offsets = placements.iter().map(|p| direction.offset(p.x, p.y));
+    let first = offsets.clone().min().unwrap_or(0);
+    let last = offsets.max().unwrap_or(0);
+    (first, last)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveError {
+    GameOver,
+    NoSuchPlayer(usize),
+    NotYourTurn(usize),
+    BagTooSmall(usize),
+    NoTiles,
+    OutOfBounds { x: u32, y: u32 },
+    Occupied { x: u32, y: u32 },
+    DuplicateSquare { x: u32, y: u32 },
+    NotInLine,
+    Gap { x: u32, y: u32 },
+    NotConnected,
+    MissesCentre,
+    OpeningTooShort,
+    NotALetter(Letter),
+    UndesignatedBlank { x: u32, y: u32 },
+    NotInHand(String),
+    InvalidWord(String),
+    NothingToChallenge,
+    OwnPlay,
+    NoDictionary,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::GameOver => write!(f, "The game is over"),
+            MoveError::NoSuchPlayer(player) => write!(f, "There is no player in seat {}", player),
+            MoveError::NotYourTurn(player) => write!(f, "It is not seat {}'s turn", player),
+            MoveError::BagTooSmall(tiles) => {
+                write!(f, "Tiles can't be exchanged with only {} in the bag", tiles)
+            }
+            MoveError::NoTiles => write!(f, "No tiles were placed"),
+            MoveError::OutOfBounds { x, y } => write!(f, "Square {},{} is off the board", x, y),
+            MoveError::Occupied { x, y } => write!(f, "Square {},{} already has a tile", x, y),
+            MoveError::DuplicateSquare { x, y } => {
+                write!(f, "More than one tile was placed on {},{}", x, y)
+            }
+            MoveError::NotInLine => write!(f, "Tiles must be placed in a single row or column"),
+            MoveError::Gap { x, y } => write!(f, "Square {},{} leaves a gap in the word", x, y),
+            MoveError::NotConnected => write!(f, "Tiles must connect to a word on the board"),
+            MoveError::MissesCentre => write!(f, "The first word must cover the centre square"),
+            MoveError::OpeningTooShort => write!(f, "The first word must be at least two letters"),
+            MoveError::NotALetter(letter) => {
+                write!(f, "Tile {} is not in this game's alphabet", letter.index())
+            }
+            MoveError::UndesignatedBlank { x, y } => {
+                write!(f, "The blank on {},{} needs a letter", x, y)
+            }
+            MoveError::NotInHand(tile) => write!(f, "There is no {} in your hand", tile),
+            MoveError::InvalidWord(word) => write!(f, "{} is not a valid word", word),
+            MoveError::NothingToChallenge => write!(f, "There is no play to challenge"),
+            MoveError::OwnPlay => write!(f, "You can't challenge your own play"),
+            MoveError::NoDictionary => write!(f, "This game has no word list to check against"),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Play(Vec<Placement>),
+    Pass,
+    Exchange(Vec<Letter>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JoinError {
+    TableFull,
+    TeamFull(usize),
+    NoSuchTeam(usize),
+    AlreadySeated(usize),
+}
+
+impl fmt::Display for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JoinError::TableFull => write!(f, "Every seat at the table is taken"),
+            JoinError::TeamFull(team) => write!(f, "Team {} has no seats left", team + 1),
+            JoinError::NoSuchTeam(team) => write!(f, "There is no team {}", team + 1),
+            JoinError::AlreadySeated(seat) => {
+                write!(f, "You are already sitting in seat {}", seat + 1)
+            }
+        }
+    }
+}
+
+impl std::error::Error for JoinError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    Standard,
+    // Words can be played in any order of their letters. Computer players and hints still
+    // only find words as they are spelt, which are always allowed too.
+    Clabbers,
+}
+
+impl FromStr for Variant {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "standard" => Ok(Variant::Standard),
+            "clabbers" => Ok(Variant::Clabbers),
+            _ => Err(format!("Unknown variant {:?}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChallengeRule {
+    // Phony words are rejected as soon as they are played
+    Void,
+    // A failed challenge costs the challenger nothing
+    Single,
+    // A failed challenge costs the challenger their next turn
+    Double,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChallengeOutcome {
+    Phony { words: Vec<String> },
+    Valid { forfeit: bool },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingPlay {
+    pub player: usize,
+    pub placements: Vec<Placement>,
+    pub score: Score,
+    pub(crate) went_out: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndReason {
+    // The seat that used their last tile once the bag was empty
+    WentOut(usize),
+    Scoreless,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Standing {
+    pub seat: usize,
+    pub username: String,
+    // Scores can end below zero once tiles left in hand and time penalties are taken off
+    pub score: i32,
+    // Points gained or lost from the tiles left in hands
+    pub adjustment: i32,
+    // Points lost for going over time
+    pub time_penalty: u32,
+    pub team: Option<usize>,
+    // Partners share their rank, which goes by their team's score
+    pub rank: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Standings {
+    pub reason: EndReason,
+    // Ordered from first place, tied players share a rank
+    pub standings: Vec<Standing>,
+}
+
+impl Standings {
+    // The combined score of a team's players, or the player's own score without teams
+    pub fn side_score(&self, standing: &Standing) -> i32 {
+        match standing.team {
+            Some(team) => self
+                .standings
+                .iter()
+                .filter(|s| s.team == Some(team))
+                .map(|s| s.score)
+                .sum(),
+            None => standing.score,
+        }
+    }
+
+    // A player's rank is one more than the number of sides ahead of them, with each team
+    // counted once
+    fn rank(&mut self) {
+        let scores: Vec<i32> = self.standings.iter().map(|s| self.side_score(s)).collect();
+        let sides: Vec<usize> = (0..self.standings.len())
+            .filter(|index| {
+                let team = self.standings[*index].team;
+                team.is_none() || self.standings[..*index].iter().all(|s| s.team != team)
+            })
+            .collect();
+        for (index, score) in scores.iter().enumerate() {
+            let ahead = sides.iter().filter(|side| scores[**side] > *score).count();
+            self.standings[index].rank = 1 + ahead as u8;
+        }
+        self.standings.sort_by_key(|s| (s.rank, s.team));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Square {
+    pub premium: Premium,
+    pub tile: Option<Tile>,
+}
+
+impl Square {
+    pub fn is_empty(&self) -> bool {
+        self.tile.is_none()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Board {
+    width: u32,
+    height: u32,
+    centre: (u32, u32),
+    squares: Vec<Square>,
+}
+
+impl Board {
+    pub fn new() -> Self {
+        Board::from_layout(&Rules::default().layout)
+    }
+
+    pub fn from_layout<S: AsRef<str>>(layout: &[S]) -> Self {
+        let height = layout.len() as u32;
+        let width = layout.first().map_or(0, |row| row.as_ref().chars().count()) as u32;
+        let mut centre = (width / 2, height / 2);
+        let mut squares = Vec::with_capacity((width * height) as usize);
+        for (y, row) in layout.iter().map(|row| row.as_ref()).enumerate() {
+            assert_eq!(
+                row.chars().count() as u32,
+                width,
+                "Layout row {} has the wrong width",
+                y
+            );
+            for (x, symbol) in row.chars().enumerate() {
+                if symbol == '*' {
+                    centre = (x as u32, y as u32);
+                }
+                let premium = Premium::from_layout(symbol)
+                    .unwrap_or_else(|| panic!("Unknown layout symbol {:?} at {},{}", symbol, x, y));
+                squares.push(Square {
+                    premium,
+                    tile: None,
+                });
+            }
+        }
+        Board {
+            width,
+            height,
+            centre,
+            squares,
         }
     }
 
-    fn draw(&mut self, player: &mut Player) -> bool {
-        let index: usize = thread_rng().gen_range(0..self.bag.len() - 1);
-        if let Some(character) = self.bag.iter_mut().nth(index) {
-            player.insert_into_hand(*character)
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn centre(&self) -> (u32, u32) {
+        self.centre
+    }
+
+    pub fn in_bounds(&self, x: u32, y: u32) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Option<&Square> {
+        if self.in_bounds(x, y) {
+            self.squares.get((x + y * self.width) as usize)
         } else {
-            false
+            None
+        }
+    }
+
+    fn get_mut(&mut self, x: u32, y: u32) -> Option<&mut Square> {
+        if self.in_bounds(x, y) {
+            self.squares.get_mut((x + y * self.width) as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn tile_at(&self, x: u32, y: u32) -> Option<Tile> {
+        self.get(x, y).and_then(|square| square.tile)
+    }
+
+    pub fn premium_at(&self, x: u32, y: u32) -> Premium {
+        self.get(x, y)
+            .map(|square| square.premium)
+            .unwrap_or(Premium::None)
+    }
+
+    pub fn is_occupied(&self, x: u32, y: u32) -> bool {
+        self.tile_at(x, y).is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.squares.iter().all(|square| square.is_empty())
+    }
+
+    pub fn has_neighbour(&self, x: u32, y: u32) -> bool {
+        (x > 0 && self.is_occupied(x - 1, y))
+            || self.is_occupied(x + 1, y)
+            || (y > 0 && self.is_occupied(x, y - 1))
+            || self.is_occupied(x, y + 1)
+    }
+
+    pub fn place(&mut self, x: u32, y: u32, tile: Tile) -> bool {
+        match self.get_mut(x, y) {
+            Some(square) if square.is_empty() => {
+                square.tile = Some(tile);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn remove(&mut self, x: u32, y: u32) -> Option<Tile> {
+        self.get_mut(x, y).and_then(|square| square.tile.take())
+    }
+
+    // Every word of two or more letters made by putting `placements` on the board, the main
+    // word first followed by any cross-words
+    pub fn words_formed(&self, placements: &[Placement]) -> Vec<Word> {
+        let direction = match placement_direction(placements) {
+            Some(direction) => direction,
+            None => return vec![],
+        };
+        let mut words = vec![];
+        let first = &placements[0];
+        if let Some(word) = self.word_through(placements, first.x, first.y, direction) {
+            words.push(word);
+        }
+        for placement in placements {
+            if let Some(word) = self.word_through(
+                placements,
+                placement.x,
+                placement.y,
+                direction.perpendicular(),
+            ) {
+                words.push(word);
+            }
+        }
+        words
+    }
+
+    fn word_through(
+        &self,
+        placements: &[Placement],
+        x: u32,
+        y: u32,
+        direction: Direction,
+    ) -> Option<Word> {
+        let tile_at = |offset: u32| {
+            let (x, y) = direction.at(x, y, offset);
+            if let Some(tile) = self.tile_at(x, y) {
+                Some((x, y, tile, false))
+            } else {
+                placements
+                    .iter()
+                    .find(|p| p.x == x && p.y == y)
+                    .map(|p| (x, y, p.tile, true))
+            }
+        };
+        let mut start = direction.offset(x, y);
+        while start > 0 && tile_at(start - 1).is_some() {
+            start -= 1;
         }
+        let mut tiles = vec![];
+        let mut offset = start;
+        while let Some(tile) = tile_at(offset) {
+            tiles.push(tile);
+            offset += 1;
+        }
+        if tiles.len() < 2 {
+            return None;
+        }
+        let (x, y) = direction.at(x, y, start);
+        Some(Word {
+            x,
+            y,
+            direction,
+            tiles,
+        })
+    }
+
+    pub fn tiles(&self) -> impl Iterator<Item = (u32, u32, Tile)> + '_ {
+        let width = self.width;
+        self.squares
+            .iter()
+            .enumerate()
+            .filter_map(move |(i, square)| {
+                square
+                    .tile
+                    .map(|tile| (i as u32 % width, i as u32 / width, tile))
+            })
+    }
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Board::new()
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct Player {
-    username: &'static str,
-    score: u8,
-    hand: Vec<char>,
+    pub(crate) identity: Identity,
+    pub(crate) score: i32,
+    pub(crate) hand: Vec<Letter>,
+    pub(crate) computer: Option<Difficulty>,
 }
 
 impl Player {
-    pub fn new(username: &'static str) -> Self {
+    pub fn new(identity: Identity) -> Self {
         Player {
-            username,
+            identity,
             score: 0,
             hand: vec![],
+            computer: None,
         }
     }
-    fn insert_into_hand(&mut self, character: char) -> bool {
-        if self.hand.len() < 7 {
-            self.hand.push(character);
-            true
-        } else {
-            false
+
+    pub fn computer(identity: Identity, difficulty: Difficulty) -> Self {
+        Player {
+            computer: Some(difficulty),
+            ..Player::new(identity)
+        }
+    }
+
+    pub fn id(&self) -> PlayerId {
+        self.identity.id()
+    }
+
+    pub fn identity(&self) -> &Identity {
+        &self.identity
+    }
+
+    pub fn is_computer(&self) -> bool {
+        self.computer.is_some()
+    }
+
+    pub fn difficulty(&self) -> Option<Difficulty> {
+        self.computer
+    }
+
+    pub fn username(&self) -> &str {
+        self.identity.name()
+    }
+
+    pub fn score(&self) -> i32 {
+        self.score
+    }
+
+    pub fn hand(&self) -> &[Letter] {
+        &self.hand
+    }
+
+    pub fn hand_value(&self, rules: &Rules) -> u32 {
+        self.hand.iter().map(|c| rules.letter_score(*c)).sum()
+    }
+
+    fn take_from_hand(&mut self, tile: Letter) -> bool {
+        match self.hand.iter().position(|t| *t == tile) {
+            Some(index) => {
+                self.hand.remove(index);
+                true
+            }
+            None => false,
         }
     }
+
+    fn insert_into_hand(&mut self, tile: Letter) {
+        self.hand.push(tile);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordScore {
+    pub word: String,
+    pub letters: Vec<Letter>,
+    pub x: u32,
+    pub y: u32,
+    pub direction: Direction,
+    pub score: u32,
 }
 
-fn get_character_score(character: char) -> u8 {
-    match character {
-        'E' | 'A' | 'I' | 'O' | 'N' | 'R' | 'T' | 'L' | 'S' | 'U' => 1,
-        'D' | 'G' => 2,
-        'B' | 'C' | 'M' | 'P' => 3,
-        'F' | 'H' | 'V' | 'W' | 'Y' => 4,
-        'K' => 5,
-        'J' | 'X' => 8,
-        'Q' | 'Z' => 10,
-        _ => 0,
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Score {
+    pub words: Vec<WordScore>,
+    pub bingo: u32,
+    pub total: u32,
+}
+
+// Premiums only count for the squares covered by this move, tiles already on the board
+// are scored at face value
+pub(crate) fn calculate_score_from_placement(
+    board: &Board,
+    rules: &Rules,
+    placements: &[Placement],
+) -> Score {
+    let mut score = Score::default();
+    for word in board.words_formed(placements) {
+        let mut word_score = 0;
+        let mut word_multiplier = 1;
+        for (x, y, tile, new) in &word.tiles {
+            let mut tile_score = rules.tile_score(*tile);
+            if *new {
+                let premium = board.premium_at(*x, *y);
+                tile_score *= premium.letter_multiplier();
+                word_multiplier *= premium.word_multiplier();
+            }
+            word_score += tile_score;
+        }
+        word_score *= word_multiplier;
+        score.total += word_score;
+        score.words.push(WordScore {
+            word: word.text(&rules.alphabet),
+            letters: word.letters(),
+            x: word.x,
+            y: word.y,
+            direction: word.direction,
+            score: word_score,
+        });
+    }
+    if placements.len() == rules.rack_size {
+        score.bingo = rules.bingo_bonus;
+        score.total += rules.bingo_bonus;
     }
+    score
 }
 
-fn calculate_score_from_placement(characters: Vec<char>) -> u8 {
-    characters
-        .iter()
-        .map(|c| get_character_score(*c))
-        .collect::<Vec<u8>>()
-        .iter()
-        .sum()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn two_player_game(seed: u64) -> GameState {
+        let mut game = GameState::new(0, Rules::default(), Some(seed));
+        for (id, name) in [(1, "Ann"), (2, "Bob")] {
+            let identity = Identity::new(PlayerId::new(id), name).unwrap();
+            game.add_player(Player::new(identity)).unwrap();
+        }
+        game
+    }
+
+    // Tiles spelling `word` from (x, y), reading across or down
+    fn spell(game: &GameState, x: u32, y: u32, direction: Direction, word: &str) -> Vec<Placement> {
+        let letters = game.rules().alphabet.parse(word).unwrap();
+        letters
+            .iter()
+            .enumerate()
+            .map(|(i, letter)| {
+                let (x, y) = direction.at(x, y, direction.offset(x, y) + i as u32);
+                Placement::new(x, y, Tile::new(*letter))
+            })
+            .collect()
+    }
+
+    fn give(game: &mut GameState, seat: usize, tiles: &str) {
+        let tiles = game.rules().alphabet.parse(tiles).unwrap();
+        game.players[seat].as_mut().unwrap().hand = tiles;
+    }
+
+    #[test]
+    fn opening_play_covers_the_centre() {
+        let mut game = two_player_game(1);
+        give(&mut game, 0, "CATS");
+        let play = spell(&game, 7, 7, Direction::Across, "CAT");
+        assert_eq!(game.validate_move(0, &play), Ok(()));
+        let play = spell(&game, 0, 0, Direction::Across, "CAT");
+        assert_eq!(game.validate_move(0, &play), Err(MoveError::MissesCentre));
+        let play = spell(&game, 7, 7, Direction::Across, "C");
+        assert_eq!(
+            game.validate_move(0, &play),
+            Err(MoveError::OpeningTooShort)
+        );
+    }
+
+    #[test]
+    fn tiles_are_in_one_unbroken_line() {
+        let mut game = two_player_game(1);
+        give(&mut game, 0, "CATS");
+        let mut play = spell(&game, 7, 7, Direction::Across, "CAT");
+        play.remove(1);
+        assert_eq!(
+            game.validate_move(0, &play),
+            Err(MoveError::Gap { x: 8, y: 7 })
+        );
+        play[1].y += 1;
+        assert_eq!(game.validate_move(0, &play), Err(MoveError::NotInLine));
+        let play = spell(&game, 7, 7, Direction::Down, "CAT");
+        assert_eq!(game.validate_move(0, &play), Ok(()));
+    }
+
+    #[test]
+    fn later_plays_connect_to_the_board() {
+        let mut game = two_player_game(1);
+        play_cat(&mut game);
+        give(&mut game, 1, "DOGS");
+        let play = spell(&game, 0, 0, Direction::Across, "DOG");
+        assert_eq!(game.validate_move(1, &play), Err(MoveError::NotConnected));
+        // Extending a word on the board leaves no gap
+        let play = spell(&game, 10, 7, Direction::Across, "S");
+        assert_eq!(game.validate_move(1, &play), Ok(()));
+        let play = spell(&game, 8, 6, Direction::Down, "D");
+        assert_eq!(game.validate_move(1, &play), Ok(()));
+        let play = spell(&game, 7, 7, Direction::Down, "DOG");
+        assert_eq!(
+            game.validate_move(1, &play),
+            Err(MoveError::Occupied { x: 7, y: 7 })
+        );
+        let play = spell(&game, 10, 7, Direction::Across, "X");
+        assert_eq!(
+            game.validate_move(1, &play),
+            Err(MoveError::NotInHand("X".to_string()))
+        );
+    }
+
+    fn play_cat(game: &mut GameState) {
+        give(game, 0, "CAT");
+        let play = spell(game, 7, 7, Direction::Across, "CAT");
+        game.play(0, &play).unwrap();
+        game.accept_play(1).unwrap();
+    }
+
+    #[test]
+    fn only_opponents_accept_a_play() {
+        let mut game = two_player_game(1);
+        game.set_challenge_rule(ChallengeRule::Single);
+        game.set_dictionary(Arc::new(Dictionary::from_words(
+            ["CAT"],
+            &game.rules().alphabet,
+        )));
+        give(&mut game, 0, "TAC");
+        game.play(0, &spell(&game, 7, 7, Direction::Across, "TAC"))
+            .unwrap();
+        assert_eq!(game.accept_play(0), Err(MoveError::OwnPlay));
+        assert_eq!(game.accept_play(2), Err(MoveError::NoSuchPlayer(2)));
+        assert_eq!(
+            game.challenge(1),
+            Ok(ChallengeOutcome::Phony {
+                words: vec!["TAC".to_string()]
+            })
+        );
+        assert!(game.board().is_empty());
+    }
+
+    #[test]
+    fn increments_are_only_added_for_turns_played() {
+        let mut game = two_player_game(1);
+        let minute = Duration::from_secs(60);
+        let increment = Duration::from_secs(5);
+        game.set_time_control(Some(TimeControl::new(minute, increment)));
+        game.set_challenge_rule(ChallengeRule::Double);
+        game.set_dictionary(Arc::new(Dictionary::from_words(
+            ["CAT"],
+            &game.rules().alphabet,
+        )));
+        let remaining = |game: &GameState, seat| game.clock(seat).unwrap().remaining();
+        give(&mut game, 0, "TAC");
+        game.play(0, &spell(&game, 7, 7, Direction::Across, "TAC"))
+            .unwrap();
+        game.challenge(1).unwrap();
+        assert_eq!(remaining(&game, 0), minute + increment);
+
+        give(&mut game, 1, "CAT");
+        game.play(1, &spell(&game, 7, 7, Direction::Across, "CAT"))
+            .unwrap();
+        // Seat 0 loses the challenge and with it the turn it was about to take
+        game.challenge(0).unwrap();
+        assert_eq!(game.current_player(), 1);
+        assert_eq!(remaining(&game, 0), minute + increment);
+        assert_eq!(remaining(&game, 1), minute + increment);
+    }
+
+    #[test]
+    fn opening_on_the_centre_doubles() {
+        let game = two_player_game(1);
+        // H8 CAT
+        let score = game.score_move(&spell(&game, 7, 7, Direction::Across, "CAT"));
+        assert_eq!(score.total, 10);
+        assert_eq!(score.words.len(), 1);
+        let score = game.score_move(&spell(&game, 7, 7, Direction::Across, "RETAINS"));
+        assert_eq!(score.bingo, 50);
+        assert_eq!(score.total, 66);
+    }
+
+    #[test]
+    fn premiums_count_once() {
+        let mut game = two_player_game(1);
+        play_cat(&mut game);
+        // The letters either side of the A are on double letter squares
+        let mut play = spell(&game, 8, 6, Direction::Down, "TAX");
+        play.remove(1);
+        assert_eq!(game.score_move(&play).total, 19);
+        // The centre has already been used
+        let mut play = spell(&game, 7, 6, Direction::Down, "ACT");
+        play.remove(1);
+        assert_eq!(game.score_move(&play).total, 5);
+    }
+
+    #[test]
+    fn cross_words_are_scored() {
+        let mut game = two_player_game(1);
+        play_cat(&mut game);
+        let score = game.score_move(&spell(&game, 8, 8, Direction::Across, "XI"));
+        let words: Vec<(&str, u32)> = score
+            .words
+            .iter()
+            .map(|word| (word.word.as_str(), word.score))
+            .collect();
+        assert_eq!(words.len(), 3);
+        assert!(words.contains(&("XI", 17)));
+        assert!(words.contains(&("AX", 17)));
+        assert!(words.contains(&("TI", 2)));
+        assert_eq!(score.total, 36);
+    }
+
+    #[test]
+    fn bags_with_the_same_seed_draw_the_same_tiles() {
+        let tiles = Rules::default().alphabet.bag();
+        let mut first = Bag::new(&tiles, 42);
+        let mut second = Bag::new(&tiles, 42);
+        let draws: Vec<Letter> = std::iter::from_fn(|| first.draw()).collect();
+        assert_eq!(draws.len(), tiles.len());
+        assert_eq!(
+            draws,
+            std::iter::from_fn(|| second.draw()).collect::<Vec<_>>()
+        );
+        let mut other = Bag::new(&tiles, 43);
+        assert_ne!(
+            draws,
+            std::iter::from_fn(|| other.draw()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn games_with_the_same_seed_deal_the_same_hands() {
+        let first = two_player_game(42);
+        let second = two_player_game(42);
+        for seat in 0..2 {
+            assert_eq!(
+                first.player(seat).unwrap().hand(),
+                second.player(seat).unwrap().hand()
+            );
+        }
+        assert_eq!(first.bag(), second.bag());
+    }
+
+    #[test]
+    fn scores_can_finish_below_zero() {
+        let mut game = two_player_game(7);
+        let values: Vec<i32> = (0..2)
+            .map(|seat| game.player(seat).unwrap().hand_value(game.rules()) as i32)
+            .collect();
+        assert_ne!(values[0], values[1]);
+        for _ in 0..MAX_SCORELESS_TURNS {
+            game.pass(game.current_player()).unwrap();
+        }
+        let standings = game.standings().unwrap();
+        for standing in &standings.standings {
+            assert_eq!(standing.score, -values[standing.seat]);
+        }
+        // Whoever was left holding less comes first rather than tying at zero
+        let winner = if values[0] < values[1] { 0 } else { 1 };
+        assert_eq!(standings.standings[0].seat, winner);
+        assert_eq!(standings.standings[0].rank, 1);
+        assert_eq!(standings.standings[1].rank, 2);
+    }
 }
@@ -0,0 +1,716 @@
+use crate::alphabet::{Alphabet, Letter};
+use crate::game::{
+    Bag, ChallengeRule, Direction, GameState, MoveError, Placement, Player, Score, Tile,
+};
+use crate::history::{Move, MoveKind};
+use crate::identity::{Identity, NameError, PlayerId};
+use crate::rules::Rules;
+use std::fmt;
+
+// GCG is the plain text format used by other word game tools to share annotated games, see
+// https://www.poslfit.com/scrabble/gcg/. Each move is a line like
+//
+//   >alice: AEINRST 8D RETAINS +74 74
+//
+// with the rack before the move, where the word starts (row first for across, column first for
+// down), the word with '.' for tiles already on the board and lowercase letters for blanks, the
+// move's score and the player's total. Tiles with more than one character are written in
+// brackets like [LL].
+
+#[derive(Debug)]
+pub struct GcgError {
+    pub line: usize,
+    pub reason: String,
+}
+
+impl GcgError {
+    fn new(line: usize, reason: impl Into<String>) -> Self {
+        GcgError {
+            line,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for GcgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.reason)
+    }
+}
+
+impl std::error::Error for GcgError {}
+
+impl GameState {
+    // Writes out every move made so far, games don't have to be finished
+    pub fn to_gcg(&self) -> String {
+        let alphabet = &self.rules.alphabet;
+        let nicknames = self.nicknames();
+        let mut text = format!(
+            "#character-encoding UTF-8\n#id worders {}\n#description {} ruleset\n",
+            self.id, self.rules.name
+        );
+        for (seat, player) in self.players.iter().enumerate() {
+            if let Some(player) = player {
+                text.push_str(&format!(
+                    "#player{} {} {}\n",
+                    seat + 1,
+                    nicknames[seat],
+                    player.username()
+                ));
+            }
+        }
+        // Not part of GCG, other tools skip pragmas they don't know
+        if self.team_play {
+            text.push_str("#teams partners sit in every other seat\n");
+        }
+
+        let mut last_play: Option<&Move> = None;
+        for entry in &self.history {
+            let nickname = &nicknames[entry.player];
+            let rack = rack_text(alphabet, &entry.rack);
+            let line = match &entry.kind {
+                MoveKind::Play { placements, score } => {
+                    last_play = Some(entry);
+                    match play_text(self, placements, score) {
+                        Some(play) => format!("{} {} +{}", rack, play, score.total),
+                        None => continue,
+                    }
+                }
+                MoveKind::Pass | MoveKind::LostTurn => format!("{} - +0", rack),
+                MoveKind::Exchange { tiles } => {
+                    format!("{} -{} +0", rack, rack_text(alphabet, tiles))
+                }
+                // Shown with the rack the phony was played from
+                MoveKind::PhonyRemoved { .. } => {
+                    let rack = last_play
+                        .filter(|play| play.player == entry.player)
+                        .map_or(rack, |play| rack_text(alphabet, &play.rack));
+                    format!("{} -- -{}", rack, entry.score_before - entry.score_after)
+                }
+                MoveKind::ChallengeFailed { challenged, .. } => {
+                    text.push_str(&format!(
+                        "#note {} challenged {}'s play, which stood\n",
+                        nickname, nicknames[*challenged]
+                    ));
+                    continue;
+                }
+                // The player who went out is given the other side's tiles
+                MoveKind::EndRack { adjustment } if *adjustment > 0 || entry.rack.is_empty() => {
+                    let others: Vec<Letter> = self
+                        .history
+                        .iter()
+                        .filter(|other| !self.same_team(other.player, entry.player))
+                        .filter(|other| matches!(other.kind, MoveKind::EndRack { .. }))
+                        .flat_map(|other| other.rack.iter().copied())
+                        .collect();
+                    format!(" ({}) +{}", rack_text(alphabet, &others), adjustment)
+                }
+                MoveKind::EndRack { adjustment } => {
+                    format!("{} ({}) -{}", rack, rack, adjustment.unsigned_abs())
+                }
+                MoveKind::TimePenalty { points } => format!("{} (time) -{}", rack, points),
+            };
+            text.push_str(&format!(">{}: {} {}\n", nickname, line, entry.score_after));
+        }
+        text
+    }
+
+    // Replays the moves of a GCG file with the given rules. Players are dealt the racks shown
+    // in the file and the bag is arranged so they draw the tiles their next rack shows, so the
+    // history matches the original game. Words aren't checked, and the game has no dictionary
+    // set.
+    pub fn from_gcg(text: &str, mut rules: Rules) -> Result<Self, GcgError> {
+        let lines: Vec<(usize, &str)> = text
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .collect();
+
+        // Players are seated in the order of their #player lines, and anyone else in the order
+        // they first move
+        let mut players: Vec<(String, Identity)> = vec![];
+        for (number, line) in &lines {
+            if let Some(rest) = line.strip_prefix("#player") {
+                let mut parts = rest.splitn(3, char::is_whitespace);
+                let _seat = parts.next();
+                let nickname = parts
+                    .next()
+                    .filter(|nickname| !nickname.is_empty())
+                    .ok_or_else(|| GcgError::new(*number, "The player has no nickname"))?;
+                let name = parts.next().unwrap_or(nickname);
+                let identity = gcg_identity(players.len(), name, nickname)
+                    .map_err(|e| GcgError::new(*number, e.to_string()))?;
+                players.push((nickname.to_string(), identity));
+            }
+        }
+        let mut turns = vec![];
+        for (number, line) in &lines {
+            let rest = match line.strip_prefix('>') {
+                Some(rest) => rest,
+                None => continue,
+            };
+            let (nickname, rest) = rest
+                .split_once(':')
+                .ok_or_else(|| GcgError::new(*number, "Expected the player's name and ':'"))?;
+            let seat = match players.iter().position(|(n, _)| n == nickname) {
+                Some(seat) => seat,
+                None => {
+                    let identity = gcg_identity(players.len(), nickname, nickname)
+                        .map_err(|e| GcgError::new(*number, e.to_string()))?;
+                    players.push((nickname.to_string(), identity));
+                    players.len() - 1
+                }
+            };
+            let mut tokens: Vec<&str> = rest.split_whitespace().collect();
+            // The rack can be left out when it wasn't known
+            let rack = match tokens.first() {
+                Some(first)
+                    if !first.starts_with(['-', '(', '+']) && !first.contains(char::is_numeric) =>
+                {
+                    let rack = parse_tiles(&rules.alphabet, tokens.remove(0), '?')
+                        .map_err(|reason| GcgError::new(*number, reason))?;
+                    Some(rack.iter().map(|tile| tile.used()).collect())
+                }
+                _ => None,
+            };
+            turns.push(Turn {
+                line: *number,
+                seat,
+                rack,
+                tokens,
+            });
+        }
+        if players.is_empty() || players.len() > u8::MAX as usize {
+            return Err(GcgError::new(
+                lines.len(),
+                format!("Games need between 1 and {} players", u8::MAX),
+            ));
+        }
+
+        rules.players = players.len();
+        let mut game = GameState::new(0, rules, None);
+        // Phonies in the file are taken back off the board by a challenge, the game keeps its
+        // own rule once the moves have been replayed
+        let challenge_rule = game.challenge_rule;
+        game.challenge_rule = ChallengeRule::Single;
+        if let Some((number, _)) = lines.iter().find(|(_, line)| line.starts_with("#teams")) {
+            if !game.set_team_play(true) {
+                return Err(GcgError::new(
+                    *number,
+                    "Team games need an even number of players",
+                ));
+            }
+        }
+        for (seat, (_, identity)) in players.iter().enumerate() {
+            let first = turns.iter().find(|turn| turn.seat == seat);
+            if let Some(rack) = first.and_then(|turn| turn.rack.as_ref()) {
+                game.bag.arrange(rack);
+            }
+            game.add_player(Player::new(identity.clone()))
+                .expect("Every player has their own seat");
+        }
+        // Whoever has the first move line started, which isn't always the first seat
+        if let Some(first) = turns.first() {
+            game.turn = first.seat as u8;
+        }
+        for (index, turn) in turns.iter().enumerate() {
+            let next = turns[index + 1..]
+                .iter()
+                .find(|next| next.seat == turn.seat)
+                .filter(|next| next.action() != "--")
+                .and_then(|next| next.rack.as_deref());
+            replay(&mut game, turn, next).map_err(|reason| GcgError::new(turn.line, reason))?;
+        }
+        game.challenge_rule = challenge_rule;
+        Ok(game)
+    }
+
+    // Nicknames can't have spaces in them and have to tell the players apart
+    fn nicknames(&self) -> Vec<String> {
+        let mut nicknames: Vec<String> = vec![];
+        for (seat, player) in self.players.iter().enumerate() {
+            let mut nickname: String = player
+                .as_ref()
+                .map_or("", |p| p.username())
+                .split_whitespace()
+                .collect::<Vec<&str>>()
+                .join("_");
+            if nickname.is_empty() || nicknames.contains(&nickname) {
+                nickname.push_str(&(seat + 1).to_string());
+            }
+            nicknames.push(nickname);
+        }
+        nicknames
+    }
+
+    // A play that went out only ends the game once it can no longer be challenged
+    fn finish_pending(&mut self) {
+        if self
+            .pending
+            .as_ref()
+            .is_some_and(|pending| pending.went_out)
+        {
+            let _ = self.close_challenge();
+        }
+    }
+
+    // Deals the player exactly these tiles, taking them from the bag or, when the bag has run
+    // out, swapping them with whoever was dealt them instead
+    fn deal_rack(&mut self, seat: usize, rack: &[Letter]) -> Result<(), String> {
+        if let Some(player) = self.players[seat].as_mut() {
+            let (mut held, mut wanted) = (player.hand.clone(), rack.to_vec());
+            held.sort();
+            wanted.sort();
+            if held == wanted {
+                player.hand = rack.to_vec();
+                return Ok(());
+            }
+        }
+        let mut pool = self.bag.tiles.clone();
+        pool.extend(
+            self.players[seat]
+                .as_ref()
+                .map_or(vec![], |p| p.hand.clone()),
+        );
+        let mut missing = vec![];
+        for letter in rack {
+            match pool.iter().position(|tile| tile == letter) {
+                Some(index) => {
+                    pool.swap_remove(index);
+                }
+                None => missing.push(*letter),
+            }
+        }
+        let mut hands: Vec<Vec<Letter>> = self
+            .players
+            .iter()
+            .map(|p| p.as_ref().map_or(vec![], |p| p.hand.clone()))
+            .collect();
+        for letter in missing {
+            let holder = (0..hands.len())
+                .filter(|other| *other != seat)
+                .find_map(|other| Some((other, hands[other].iter().position(|t| *t == letter)?)));
+            match (holder, pool.pop()) {
+                (Some((other, index)), Some(replacement)) => hands[other][index] = replacement,
+                _ => {
+                    return Err(format!(
+                        "There are no {} tiles left to deal",
+                        self.rules.alphabet.describe(letter)
+                    ))
+                }
+            }
+        }
+        hands[seat] = rack.to_vec();
+        for (player, hand) in self.players.iter_mut().zip(hands) {
+            if let Some(player) = player {
+                player.hand = hand;
+            }
+        }
+        self.bag.tiles = pool;
+        Ok(())
+    }
+}
+
+// Players are numbered by seat, and go by their nickname when their full name isn't allowed
+fn gcg_identity(seat: usize, name: &str, nickname: &str) -> Result<Identity, NameError> {
+    let id = PlayerId::new(seat as u64);
+    Identity::new(id, name).or_else(|_| Identity::new(id, nickname))
+}
+
+// One move line of a GCG file
+struct Turn<'a> {
+    line: usize,
+    seat: usize,
+    // The rack before the move, if the file gives it
+    rack: Option<Vec<Letter>>,
+    tokens: Vec<&'a str>,
+}
+
+impl Turn<'_> {
+    fn action(&self) -> &str {
+        self.tokens.first().copied().unwrap_or("")
+    }
+}
+
+// `next` is the player's rack before their next move, which is what they should be left with
+// after this one
+fn replay(game: &mut GameState, turn: &Turn, next: Option<&[Letter]>) -> Result<(), String> {
+    let seat = turn.seat;
+    let action = turn.action();
+    // Time penalties are taken off once the game is over
+    if action == "(time)" {
+        game.finish_pending();
+        if !game.is_over() {
+            return Err("Time penalties come after the end of the game".to_string());
+        }
+        let points = turn.tokens.get(1).copied().unwrap_or("");
+        return match points.trim_start_matches('-').parse::<u32>() {
+            Ok(points) => {
+                game.penalise_time(seat, points);
+                Ok(())
+            }
+            Err(_) => Err(format!("{:?} isn't a penalty", points)),
+        };
+    }
+    // End of game adjustments and challenge bonuses aren't moves, the game works out the end of
+    // game adjustments itself
+    if action.starts_with('(') {
+        game.finish_pending();
+        return Ok(());
+    }
+    if game.is_over() {
+        return Err("The game is already over".to_string());
+    }
+    let move_error = |e: MoveError| e.to_string();
+    if action == "--" {
+        // The file doesn't say which words were phonies, so every word the play made is kept
+        let words = game.pending.as_ref().map_or(vec![], |pending| {
+            pending
+                .score
+                .words
+                .iter()
+                .map(|word| word.word.clone())
+                .collect()
+        });
+        return game
+            .remove_phony(seat_after(game, seat), words)
+            .map_err(move_error);
+    }
+    if game.current_player() != seat {
+        let current = game.current_player();
+        return Err(format!(
+            "It should be {}'s turn",
+            game.player(current).map_or("", |p| p.username())
+        ));
+    }
+    if let Some(rack) = &turn.rack {
+        game.deal_rack(seat, rack)?;
+    }
+    let hand = game.players[seat]
+        .as_ref()
+        .map_or(vec![], |p| p.hand.clone());
+
+    if action == "-" {
+        return game.pass(seat).map_err(move_error);
+    }
+    if let Some(exchanged) = action.strip_prefix('-') {
+        // Some files only give how many tiles were exchanged
+        let tiles: Vec<Letter> = match exchanged.parse::<usize>() {
+            Ok(count) => hand.iter().copied().take(count).collect(),
+            Err(_) => parse_tiles(&game.rules.alphabet, exchanged, '?')?
+                .iter()
+                .map(|tile| tile.used())
+                .collect(),
+        };
+        arrange_draws(&mut game.bag, &hand, &tiles, next);
+        return game.exchange(seat, &tiles).map_err(move_error);
+    }
+
+    let (position, word, claimed) = match turn.tokens.as_slice() {
+        [position, word, score, ..] => (*position, *word, *score),
+        _ => return Err("Expected where the word was played, the word and its score".to_string()),
+    };
+    let placements = parse_play(game, position, word)?;
+    let used: Vec<Letter> = placements.iter().map(|p| p.tile.used()).collect();
+    let hand = if turn.rack.is_none() {
+        // Keep whatever else was in the hand alongside the tiles that were played
+        let mut rest = hand;
+        for letter in &used {
+            if let Some(index) = rest.iter().position(|t| t == letter) {
+                rest.remove(index);
+            }
+        }
+        rest.truncate(game.rules.rack_size.saturating_sub(used.len()));
+        let rack: Vec<Letter> = used.iter().copied().chain(rest).collect();
+        game.deal_rack(seat, &rack)?;
+        rack
+    } else {
+        hand
+    };
+    arrange_draws(&mut game.bag, &hand, &used, next);
+    let score = game.play(seat, &placements).map_err(move_error)?;
+    match claimed.trim_start_matches('+').parse::<u32>() {
+        Ok(claimed) if claimed == score.total => Ok(()),
+        Ok(claimed) => Err(format!(
+            "The play scores {} with these rules but the file gives {}",
+            score.total, claimed
+        )),
+        Err(_) => Err(format!("{:?} isn't a score", claimed)),
+    }
+}
+
+// Sets the bag up so the tiles drawn after spending some of the rack leave the player with the
+// next rack, files that don't add up are left to random draws
+fn arrange_draws(bag: &mut Bag, rack: &[Letter], spent: &[Letter], next: Option<&[Letter]>) {
+    let next = match next {
+        Some(next) => next,
+        None => return,
+    };
+    let mut draws = next.to_vec();
+    let mut kept = rack.to_vec();
+    for letter in spent {
+        if let Some(index) = kept.iter().position(|t| t == letter) {
+            kept.remove(index);
+        }
+    }
+    for letter in kept {
+        match draws.iter().position(|t| *t == letter) {
+            Some(index) => {
+                draws.remove(index);
+            }
+            None => return,
+        }
+    }
+    bag.arrange(&draws);
+}
+
+// A phony is taken off by the next player challenging it
+fn seat_after(game: &GameState, seat: usize) -> usize {
+    match game.pending_play() {
+        Some(pending) if pending.player == seat => game.current_player(),
+        _ => seat,
+    }
+}
+
+fn parse_play(game: &GameState, position: &str, word: &str) -> Result<Vec<Placement>, String> {
+    let (x, y, direction) = parse_position(position)
+        .filter(|(x, y, _)| game.board.in_bounds(*x, *y))
+        .ok_or_else(|| format!("{:?} isn't a square on the board", position))?;
+    let alphabet = &game.rules.alphabet;
+    let mut placements = vec![];
+    let mut offset = direction.offset(x, y);
+    let mut rest = word;
+    let mut through = false;
+    while !rest.is_empty() {
+        let (square_x, square_y) = direction.at(x, y, offset);
+        if let Some(after) = rest.strip_prefix('(') {
+            through = true;
+            rest = after;
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix(')') {
+            through = false;
+            rest = after;
+            continue;
+        }
+        let (token, after) = next_token(rest)
+            .ok_or_else(|| format!("{:?} isn't a word that can be played", word))?;
+        rest = after;
+        if token == "." || through {
+            if !game.board.is_occupied(square_x, square_y) {
+                return Err(format!(
+                    "The word goes through {} but there's no tile there",
+                    square_name(square_x, square_y)
+                ));
+            }
+        } else {
+            placements.push(Placement::new(
+                square_x,
+                square_y,
+                parse_tile(alphabet, token, None)?,
+            ));
+        }
+        offset += 1;
+    }
+    Ok(placements)
+}
+
+// "8D" is across from row 8 column D and "D8" is down from the same square
+fn parse_position(position: &str) -> Option<(u32, u32, Direction)> {
+    let digits = position.find(|c: char| c.is_ascii_digit())?;
+    let (direction, column, row) = if digits == 0 {
+        let letters = position.find(|c: char| c.is_ascii_alphabetic())?;
+        let (row, column) = position.split_at(letters);
+        (Direction::Across, column, row)
+    } else {
+        let (column, row) = position.split_at(digits);
+        (Direction::Down, column, row)
+    };
+    let row: u32 = row.parse().ok()?;
+    if row == 0 || column.is_empty() || !column.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    // Long columns are off any board, they are refused rather than overflowing
+    let column = column
+        .to_ascii_uppercase()
+        .bytes()
+        .try_fold(0u32, |total, c| {
+            total.checked_mul(26)?.checked_add((c - b'A') as u32 + 1)
+        })?;
+    Some((column - 1, row - 1, direction))
+}
+
+fn column_name(x: u32) -> String {
+    let mut name = vec![];
+    let mut column = x + 1;
+    while column > 0 {
+        name.push((b'A' + ((column - 1) % 26) as u8) as char);
+        column = (column - 1) / 26;
+    }
+    name.iter().rev().collect()
+}
+
+fn square_name(x: u32, y: u32) -> String {
+    format!("{}{}", y + 1, column_name(x))
+}
+
+// Splits off the next tile, which is a single character or a bracketed multi-character tile
+fn next_token(text: &str) -> Option<(&str, &str)> {
+    if let Some(rest) = text.strip_prefix('[') {
+        let end = rest.find(']')?;
+        return Some((&rest[..end], &rest[end + 1..]));
+    }
+    let first = text.chars().next()?;
+    Some(text.split_at(first.len_utf8()))
+}
+
+// Lowercase tiles are blanks standing in for that letter, `blank` is the symbol for an
+// undesignated blank in a rack
+fn parse_tile(alphabet: &Alphabet, token: &str, blank: Option<char>) -> Result<Tile, String> {
+    if blank.is_some_and(|blank| token.chars().eq([blank])) {
+        return Ok(Tile::new(Letter::BLANK));
+    }
+    let letter = alphabet.letter(token).ok_or_else(|| {
+        format!(
+            "{:?} isn't a tile in the {} alphabet",
+            token,
+            alphabet.name()
+        )
+    })?;
+    if token.chars().any(char::is_lowercase) {
+        Ok(Tile::blank(letter))
+    } else {
+        Ok(Tile::new(letter))
+    }
+}
+
+fn parse_tiles(alphabet: &Alphabet, text: &str, blank: char) -> Result<Vec<Tile>, String> {
+    let mut tiles = vec![];
+    let mut rest = text;
+    while let Some((token, after)) = next_token(rest) {
+        tiles.push(parse_tile(alphabet, token, Some(blank))?);
+        rest = after;
+    }
+    Ok(tiles)
+}
+
+fn tile_text(alphabet: &Alphabet, tile: Tile) -> String {
+    let display = alphabet.display(tile.letter);
+    let display = if tile.blank {
+        display.to_lowercase()
+    } else {
+        display.to_string()
+    };
+    if display.chars().count() > 1 {
+        format!("[{}]", display)
+    } else {
+        display
+    }
+}
+
+fn rack_text(alphabet: &Alphabet, rack: &[Letter]) -> String {
+    rack.iter()
+        .map(|letter| tile_text(alphabet, Tile::new(*letter)))
+        .collect()
+}
+
+// The position and the main word of a play, which runs along the line of the tiles placed
+fn play_text(game: &GameState, placements: &[Placement], score: &Score) -> Option<String> {
+    let word = score.words.iter().find(|word| {
+        placements.iter().all(|p| {
+            let (along, across) = match word.direction {
+                Direction::Across => (p.x, p.y == word.y),
+                Direction::Down => (p.y, p.x == word.x),
+            };
+            let start = word.direction.offset(word.x, word.y);
+            across && along >= start && along < start + word.letters.len() as u32
+        })
+    })?;
+    let position = match word.direction {
+        Direction::Across => square_name(word.x, word.y),
+        Direction::Down => format!("{}{}", column_name(word.x), word.y + 1),
+    };
+    let start = word.direction.offset(word.x, word.y);
+    let text: String = (0..word.letters.len() as u32)
+        .map(|i| {
+            let (x, y) = word.direction.at(word.x, word.y, start + i);
+            match placements.iter().find(|p| p.x == x && p.y == y) {
+                Some(placement) => tile_text(&game.rules.alphabet, placement.tile),
+                None => ".".to_string(),
+            }
+        })
+        .collect();
+    Some(format!("{} {}", position, text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Difficulty;
+    use crate::dictionary::Dictionary;
+    use std::sync::Arc;
+
+    const WORDS: [&str; 24] = [
+        "AT", "TA", "AN", "NA", "IN", "IT", "TI", "TO", "ON", "NO", "RE", "ER", "ES", "ARE", "EAR",
+        "ERA", "TEA", "EAT", "ATE", "TAN", "ANT", "NET", "TEN", "RAT",
+    ];
+
+    fn scores(game: &GameState) -> Vec<i32> {
+        (0..game.seats())
+            .map(|seat| game.player(seat).unwrap().score())
+            .collect()
+    }
+
+    #[test]
+    fn exported_games_import_with_the_same_scores() {
+        let rules = Rules::default();
+        let mut game = GameState::new(0, rules.clone(), Some(5));
+        game.set_dictionary(Arc::new(Dictionary::from_words(WORDS, &rules.alphabet)));
+        game.fill_with_computers(Difficulty::Expert);
+        while game.play_computer_turn().is_some() {}
+        assert!(game.is_over());
+        assert!(game
+            .history()
+            .iter()
+            .any(|entry| matches!(entry.kind, MoveKind::Play { .. })));
+
+        let imported = GameState::from_gcg(&game.to_gcg(), rules).unwrap();
+        assert_eq!(scores(&imported), scores(&game));
+        assert_eq!(imported.standings(), game.standings());
+        let tiles: Vec<_> = game.board().tiles().collect();
+        assert_eq!(imported.board().tiles().collect::<Vec<_>>(), tiles);
+    }
+
+    #[test]
+    fn imported_plays_are_scored() {
+        let text = "#player1 ann Ann\n#player2 bob Bob\n>ann: ACTXYZQ 8H CAT +10 10\n";
+        let game = GameState::from_gcg(text, Rules::default()).unwrap();
+        assert_eq!(scores(&game), [10, 0]);
+
+        let text = "#player1 ann Ann\n>ann: 8H CAT +12 12\n";
+        let error = GameState::from_gcg(text, Rules::default()).unwrap_err();
+        assert_eq!(error.line, 2);
+    }
+
+    #[test]
+    fn removed_phonies_keep_their_words() {
+        let text = "#player1 ann Ann\n#player2 bob Bob\n\
+                    >ann: ACTXYZQ 8H TAC +10 10\n>ann: ACTXYZQ -- -10 0\n";
+        let game = GameState::from_gcg(text, Rules::default()).unwrap();
+        // New games check words as they are played
+        assert_eq!(game.challenge_rule(), ChallengeRule::Void);
+        assert!(game.board().is_empty());
+        match &game.history().last().unwrap().kind {
+            MoveKind::PhonyRemoved { words, .. } => assert_eq!(words, &["TAC"]),
+            kind => panic!("Expected the phony to be removed, not {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn long_columns_are_an_error() {
+        let text = ">ann: 8HHHHHHHHHHHHHHHHHH CAT +10 10\n";
+        let error = GameState::from_gcg(text, Rules::default()).unwrap_err();
+        assert_eq!(error.line, 1);
+        assert!(error.reason.contains("isn't a square"));
+    }
+}
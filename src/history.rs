@@ -0,0 +1,79 @@
+use crate::alphabet::Letter;
+use crate::game::{Bag, PendingPlay, Placement, Score, Standings};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveKind {
+    Play {
+        placements: Vec<Placement>,
+        score: Score,
+    },
+    Pass,
+    Exchange {
+        tiles: Vec<Letter>,
+    },
+    // The player's last play was challenged and taken back off the board
+    PhonyRemoved {
+        challenger: usize,
+        placements: Vec<Placement>,
+        words: Vec<String>,
+    },
+    // The player challenged a play that turned out to be valid
+    ChallengeFailed {
+        challenged: usize,
+        forfeit: bool,
+    },
+    // The player's turn was skipped after losing a double challenge
+    LostTurn,
+    // The value of the tiles left in the player's rack when the game ended, or everyone
+    // else's for the player who went out
+    EndRack {
+        adjustment: i32,
+    },
+    // Points taken off at the end of the game for going over time
+    TimePenalty {
+        points: u32,
+    },
+}
+
+impl MoveKind {
+    // Moves that happen as a result of another one rather than something a player did, these
+    // are undone along with the move that caused them
+    pub fn is_automatic(&self) -> bool {
+        matches!(
+            self,
+            MoveKind::LostTurn | MoveKind::EndRack { .. } | MoveKind::TimePenalty { .. }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Move {
+    pub player: usize,
+    // The player's rack before the move
+    pub rack: Vec<Letter>,
+    pub kind: MoveKind,
+    pub score_before: i32,
+    pub score_after: i32,
+    pub(crate) state: TurnState,
+}
+
+impl Move {
+    // The seat whose action caused the move, a phony is removed because of the challenger
+    pub fn made_by(&self) -> usize {
+        match self.kind {
+            MoveKind::PhonyRemoved { challenger, .. } => challenger,
+            _ => self.player,
+        }
+    }
+}
+
+// Everything about the game before a move that can't be worked out by reversing it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TurnState {
+    pub bag: Bag,
+    pub turn: u8,
+    pub scoreless_turns: u8,
+    pub pending: Option<PendingPlay>,
+    pub forfeits: Vec<bool>,
+    pub standings: Option<Standings>,
+}
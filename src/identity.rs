@@ -0,0 +1,116 @@
+use crate::ai::Difficulty;
+use std::fmt;
+
+pub const MAX_NAME_LENGTH: usize = 20;
+// Allowed in names alongside letters and digits from any language
+const NAME_PUNCTUATION: [char; 5] = [' ', '_', '-', '.', '\''];
+
+// Stays the same for a player across connections and games, display names can change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PlayerId(u64);
+
+impl PlayerId {
+    pub fn new(id: u64) -> Self {
+        PlayerId(id)
+    }
+
+    // Each difficulty of computer player has one id shared by every game it plays
+    pub fn computer(difficulty: Difficulty) -> Self {
+        PlayerId(
+            u64::MAX
+                - match difficulty {
+                    Difficulty::Beginner => 0,
+                    Difficulty::Intermediate => 1,
+                    Difficulty::Expert => 2,
+                },
+        )
+    }
+
+    // Ids kept for computer players, people can't join under them
+    pub fn is_computer(&self) -> bool {
+        [
+            Difficulty::Beginner,
+            Difficulty::Intermediate,
+            Difficulty::Expert,
+        ]
+        .into_iter()
+        .any(|difficulty| PlayerId::computer(difficulty) == *self)
+    }
+
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for PlayerId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+// Who is sitting in a seat, only made with a name that passed validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    id: PlayerId,
+    name: String,
+}
+
+impl Identity {
+    pub fn new(id: PlayerId, name: &str) -> Result<Self, NameError> {
+        Ok(Identity {
+            id,
+            name: validate_name(name)?,
+        })
+    }
+
+    pub fn id(&self) -> PlayerId {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NameError {
+    Empty,
+    TooLong(usize),
+    NotAllowed(char),
+}
+
+impl fmt::Display for NameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NameError::Empty => write!(f, "Names can't be empty"),
+            NameError::TooLong(length) => write!(
+                f,
+                "Names can be at most {} characters, not {}",
+                MAX_NAME_LENGTH, length
+            ),
+            NameError::NotAllowed(c) => write!(f, "Names can't contain {:?}", c),
+        }
+    }
+}
+
+impl std::error::Error for NameError {}
+
+// Trims the name and squashes runs of spaces, then checks what's left. Names are counted in
+// characters rather than bytes so every language gets the same length.
+pub fn validate_name(name: &str) -> Result<String, NameError> {
+    let name = name.split_whitespace().collect::<Vec<&str>>().join(" ");
+    if name.is_empty() {
+        return Err(NameError::Empty);
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| !c.is_alphanumeric() && !NAME_PUNCTUATION.contains(c))
+    {
+        return Err(NameError::NotAllowed(c));
+    }
+    let length = name.chars().count();
+    if length > MAX_NAME_LENGTH {
+        return Err(NameError::TooLong(length));
+    }
+    Ok(name)
+}
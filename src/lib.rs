@@ -1,12 +1,28 @@
+pub mod ai;
+pub mod alphabet;
+pub mod clock;
+pub mod dictionary;
 pub mod game;
+pub mod gcg;
+pub mod history;
+pub mod identity;
+pub mod movegen;
 pub mod packets;
+pub mod ratings;
+pub mod rng;
+pub mod rules;
+pub mod save;
 pub mod thread_pool;
 
 cfg_if::cfg_if! {
     if #[cfg(target_arch="wasm32")] {
         use wasm_bindgen::prelude::*;
-        use web_sys::{HtmlButtonElement};
+        use alphabet::{Alphabet, Letter};
+        use std::cell::RefCell;
+        use std::rc::Rc;
+        use web_sys::{HtmlButtonElement, MessageEvent};
         use web_sys::WebSocket;
+        use std::io::Cursor;
         use wasm_bindgen::JsCast;
         use util::*;
         use table::Table;
@@ -30,7 +46,7 @@ pub fn start() {
 
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
-pub fn connect(hash: usize, id: usize) {
+pub fn connect(hash: usize, id: usize, username: String, team: u8, spectate: bool) {
     let mut table = Table::new(15, 15, 2, None, Some("board"), None);
     let mut hand = Table::new_no_height_aspect(7, 1, 2, None, Some("hand"), None);
     let ws = WebSocket::new("ws://192.168.0.14:8080").unwrap();
@@ -40,57 +56,148 @@ pub fn connect(hash: usize, id: usize) {
     button_element.set_inner_text("Confirm");
     ws.set_binary_type(web_sys::BinaryType::Arraybuffer);
     button_element.set_disabled(true);
-    setup_closures(hash, id, ws, &mut table, &mut hand, &mut button_element);
+    let hint_button = document().create_element("button").unwrap();
+    hint_button.set_class_name("hint");
+    let mut hint_element = hint_button.dyn_into::<HtmlButtonElement>().unwrap();
+    hint_element.set_inner_text("Hint");
+    setup_closures(
+        hash,
+        id,
+        username,
+        team,
+        spectate,
+        ws,
+        &mut table,
+        &mut hand,
+        &mut button_element,
+        &mut hint_element,
+    );
     body()
         .append_child(&button_element)
         .expect("Failed to append button");
+    body()
+        .append_child(&hint_element)
+        .expect("Failed to append button");
 }
 
 #[cfg(target_arch = "wasm32")]
 fn setup_closures(
     hash: usize,
     id: usize,
+    username: String,
+    team: u8,
+    spectate: bool,
     ws: WebSocket,
     table: &mut Table,
     hand: &mut Table,
     btn: &mut HtmlButtonElement,
+    hint_btn: &mut HtmlButtonElement,
 ) {
     use packets::{PacketFrom, PacketTo};
 
-    let test = packets::PlayerState::new(
-        0,
-        0,
-        "SarahGreyWolf".to_string(),
-        vec!['S', 'A', 'R', 'A', 'H', ' ', ' '],
-        4,
-    );
-    let _ws_clone = ws.clone();
+    // The hash the page was opened with identifies the player between visits, a team of 0 lets
+    // the server pick. Spectators only watch and never take a seat.
+    let mut hello = vec![];
+    if spectate {
+        Packets::Spectate(packets::Spectate::new(id as u16))
+            .encode(&mut hello)
+            .unwrap();
+    } else {
+        Packets::Join(packets::Join::new(id as u16, hash as u64, username, team))
+            .encode(&mut hello)
+            .unwrap();
+    }
+    // Starts as English until the server sends the game's alphabet
+    let alphabet = Rc::new(RefCell::new(Alphabet::english()));
+    let ws_clone = ws.clone();
     // On Connection Established
     let onopen = Closure::wrap(Box::new(move || {
-        let mut send_buffer = vec![];
-        let test_clone = test.clone();
-        test_clone.encode(&mut send_buffer).unwrap();
-        /*ws_clone
-        .send_with_u8_array(&send_buffer.as_slice())
-        .unwrap();*/
+        ws_clone.send_with_u8_array(&hello.as_slice()).unwrap();
     }) as Box<dyn FnMut()>);
     ws.set_onopen(Some(onopen.as_ref().unchecked_ref()));
     onopen.forget();
 
+    // Packet received
+    let mut cells = table.get_cells();
+    let alphabet_clone = alphabet.clone();
+    let onmessage = Closure::wrap(Box::new(move |e: MessageEvent| {
+        if let Ok(buffer) = e.data().dyn_into::<js_sys::ArrayBuffer>() {
+            let bytes = js_sys::Uint8Array::new(&buffer).to_vec();
+            let mut cursor = Cursor::new(bytes.as_slice());
+            match Packets::decode(&mut cursor) {
+                Packets::Place(place) => {
+                    if let Some(cell) = cells
+                        .iter_mut()
+                        .find(|c| c.position == [*place.x(), *place.y()])
+                    {
+                        let alphabet = alphabet_clone.borrow();
+                        if place.tile().is_blank() {
+                            cell.set_tile("", false);
+                        } else {
+                            cell.set_tile(alphabet.display(*place.tile()), *place.blank());
+                        }
+                    }
+                }
+                Packets::AlphabetLetter(letter) => {
+                    alphabet_clone.borrow_mut().set_letter(
+                        *letter.letter(),
+                        letter.display(),
+                        *letter.score(),
+                    );
+                }
+                Packets::Hint(hint) => {
+                    console_log!(
+                        "Hint {}: {} at {},{} {} for {}",
+                        hint.rank(),
+                        hint.word(),
+                        hint.x(),
+                        hint.y(),
+                        if *hint.across() { "across" } else { "down" },
+                        hint.score()
+                    );
+                }
+                Packets::ClockState(clock) => {
+                    let seconds = clock.remaining() / 1000;
+                    console_log!(
+                        "Seat {} clock: {}:{:02}{}{}",
+                        clock.player() + 1,
+                        seconds / 60,
+                        seconds % 60,
+                        if *clock.overtime() > 0 {
+                            format!(" and {}s over", clock.overtime() / 1000)
+                        } else {
+                            String::new()
+                        },
+                        if *clock.running() { ", running" } else { "" }
+                    );
+                }
+                _ => {}
+            }
+        }
+    }) as Box<dyn FnMut(MessageEvent)>);
+    ws.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+    onmessage.forget();
+
     // Cell clicked
-    for cell in table.get_cells() {
+    for mut cell in table.get_cells() {
+        let position = cell.position;
         let ws_clone = ws.clone();
+        let alphabet = alphabet.clone();
         let onclick = Box::new(move || {
-            let mut character = ' ';
+            let mut letter = Letter::BLANK;
             if cell.element.inner_text() != "" {
-                cell.element.set_inner_text("");
+                cell.set_tile("", false);
             } else {
-                cell.element.set_inner_text("S");
-                character = 'S';
+                let alphabet = alphabet.borrow();
+                letter = alphabet
+                    .letter("S")
+                    .or_else(|| alphabet.letters().next())
+                    .unwrap_or(Letter::BLANK);
+                cell.set_tile(alphabet.display(letter), false);
             }
             let mut send_buffer = vec![];
             let placement =
-                packets::Place::new(id as u16, character, cell.position[0], cell.position[1]);
+                packets::Place::new(id as u16, letter, false, cell.position[0], cell.position[1]);
             Packets::Place(placement).encode(&mut send_buffer).unwrap();
             assert!(send_buffer.len() > 0);
             ws_clone
@@ -98,7 +205,7 @@ fn setup_closures(
                 .unwrap();
         }) as Box<dyn FnMut()>;
         table
-            .get_cell(cell.position[0], cell.position[1])
+            .get_cell(position[0], position[1])
             .set_callback(onclick);
     }
 
@@ -106,7 +213,8 @@ fn setup_closures(
     let ws_clone = ws.clone();
     let onclick = Closure::wrap(Box::new(move || {
         let mut send_buffer = vec![];
-        let confirm_packet = packets::Ack::new(id as u16, packets::AckState::Confirm);
+        let confirm_packet =
+            packets::Ack::new(id as u16, packets::AckState::Confirm, String::new());
         Packets::Ack(confirm_packet)
             .encode(&mut send_buffer)
             .unwrap();
@@ -117,4 +225,19 @@ fn setup_closures(
     }) as Box<dyn FnMut()>);
     btn.set_onclick(Some(onclick.as_ref().unchecked_ref()));
     onclick.forget();
+
+    // Hint Button Clicked
+    let ws_clone = ws.clone();
+    let onclick = Closure::wrap(Box::new(move || {
+        let mut send_buffer = vec![];
+        let hint_request = packets::HintRequest::new(id as u16, 0, 3);
+        Packets::HintRequest(hint_request)
+            .encode(&mut send_buffer)
+            .unwrap();
+        ws_clone
+            .send_with_u8_array(&send_buffer.as_slice())
+            .unwrap();
+    }) as Box<dyn FnMut()>);
+    hint_btn.set_onclick(Some(onclick.as_ref().unchecked_ref()));
+    onclick.forget();
 }
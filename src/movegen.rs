@@ -0,0 +1,303 @@
+use crate::alphabet::{Letter, MAX_LETTERS};
+use crate::dictionary::Dictionary;
+use crate::game::{calculate_score_from_placement, Board, Direction, Placement, Score, Tile};
+use crate::rules::Rules;
+use std::collections::HashSet;
+
+const ANY_LETTER: u64 = u64::MAX;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Play {
+    pub placements: Vec<Placement>,
+    pub score: Score,
+}
+
+// Every legal play for the hand on this board, found by building words out from anchor squares
+// (the empty squares touching a tile on the board) along the dictionary's trie. Blanks in the
+// hand are tried as every letter.
+pub fn generate_moves(
+    board: &Board,
+    rules: &Rules,
+    hand: &[Letter],
+    dictionary: &Dictionary,
+) -> Vec<Play> {
+    let mut generator = Generator {
+        board,
+        rules,
+        dictionary,
+        rack: Rack::new(hand),
+        direction: Direction::Across,
+        line: 0,
+        cross_checks: vec![],
+        anchor: 0,
+        placed: vec![],
+        plays: vec![],
+        single_tiles: HashSet::new(),
+    };
+    generator.generate(Direction::Across);
+    generator.generate(Direction::Down);
+    generator.plays
+}
+
+fn letter_bit(letter: Letter) -> u64 {
+    if letter.is_blank() || letter.index() as usize > MAX_LETTERS {
+        0
+    } else {
+        1 << (letter.index() - 1)
+    }
+}
+
+struct Rack {
+    // Counts indexed by letter, the blank's slot is left unused
+    letters: [u8; MAX_LETTERS + 1],
+    blanks: u8,
+}
+
+impl Rack {
+    fn new(hand: &[Letter]) -> Self {
+        let mut rack = Rack {
+            letters: [0; MAX_LETTERS + 1],
+            blanks: 0,
+        };
+        for tile in hand {
+            if tile.is_blank() {
+                rack.blanks += 1;
+            } else if let Some(count) = rack.letters.get_mut(tile.index() as usize) {
+                *count += 1;
+            }
+        }
+        rack
+    }
+
+    fn take(&mut self, letter: Letter) -> bool {
+        let count = &mut self.letters[letter.index() as usize];
+        if *count > 0 {
+            *count -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn put_back(&mut self, letter: Letter) {
+        self.letters[letter.index() as usize] += 1;
+    }
+}
+
+struct Generator<'a> {
+    board: &'a Board,
+    rules: &'a Rules,
+    dictionary: &'a Dictionary,
+    rack: Rack,
+    direction: Direction,
+    line: u32,
+    // Letters allowed on each empty square of the current line by the words crossing it
+    cross_checks: Vec<u64>,
+    anchor: u32,
+    placed: Vec<Placement>,
+    plays: Vec<Play>,
+    // A single tile makes words in both directions, so it would otherwise be found twice
+    single_tiles: HashSet<Placement>,
+}
+
+impl<'a> Generator<'a> {
+    fn lines(&self) -> u32 {
+        match self.direction {
+            Direction::Across => self.board.height(),
+            Direction::Down => self.board.width(),
+        }
+    }
+
+    fn line_length(&self) -> u32 {
+        match self.direction {
+            Direction::Across => self.board.width(),
+            Direction::Down => self.board.height(),
+        }
+    }
+
+    fn square(&self, position: u32) -> (u32, u32) {
+        self.direction.at(self.line, self.line, position)
+    }
+
+    fn tile(&self, position: u32) -> Option<Tile> {
+        let (x, y) = self.square(position);
+        self.board.tile_at(x, y)
+    }
+
+    fn is_anchor(&self, position: u32) -> bool {
+        let (x, y) = self.square(position);
+        if self.board.is_occupied(x, y) {
+            false
+        } else if self.board.is_empty() {
+            (x, y) == self.board.centre()
+        } else {
+            self.board.has_neighbour(x, y)
+        }
+    }
+
+    fn generate(&mut self, direction: Direction) {
+        self.direction = direction;
+        for line in 0..self.lines() {
+            self.line = line;
+            self.cross_checks = (0..self.line_length())
+                .map(|position| self.cross_check(position))
+                .collect();
+            for position in 0..self.line_length() {
+                if !self.is_anchor(position) {
+                    continue;
+                }
+                self.anchor = position;
+                if position > 0 && self.tile(position - 1).is_some() {
+                    // The word has to start with the tiles already to the left of the anchor
+                    let mut start = position;
+                    while start > 0 && self.tile(start - 1).is_some() {
+                        start -= 1;
+                    }
+                    let mut node = Some(self.dictionary.root());
+                    for prefix in start..position {
+                        let letter = self.tile(prefix).map_or(Letter::BLANK, |t| t.letter);
+                        node = node.and_then(|n| self.dictionary.child(n, letter));
+                    }
+                    if let Some(node) = node {
+                        self.placed.clear();
+                        self.extend_right(node, position, position - start);
+                    }
+                } else {
+                    // The left part can only use empty squares that aren't anchors themselves,
+                    // otherwise the same play would be found from more than one anchor
+                    let mut limit = 0;
+                    while limit < position
+                        && self.tile(position - limit - 1).is_none()
+                        && !self.is_anchor(position - limit - 1)
+                    {
+                        limit += 1;
+                    }
+                    self.left_part(&mut vec![], self.dictionary.root(), limit);
+                }
+            }
+        }
+    }
+
+    fn cross_check(&self, position: u32) -> u64 {
+        let (x, y) = self.square(position);
+        if self.board.is_occupied(x, y) {
+            return 0;
+        }
+        let across = self.direction.perpendicular();
+        let mut before = vec![];
+        let mut offset = across.offset(x, y);
+        while offset > 0 {
+            let (bx, by) = across.at(x, y, offset - 1);
+            match self.board.tile_at(bx, by) {
+                Some(tile) => before.insert(0, tile.letter),
+                None => break,
+            }
+            offset -= 1;
+        }
+        let mut after = vec![];
+        let mut offset = across.offset(x, y) + 1;
+        loop {
+            let (ax, ay) = across.at(x, y, offset);
+            match self.board.tile_at(ax, ay) {
+                Some(tile) => after.push(tile.letter),
+                None => break,
+            }
+            offset += 1;
+        }
+        if before.is_empty() && after.is_empty() {
+            return ANY_LETTER;
+        }
+        let mut word = before.clone();
+        word.push(Letter::BLANK);
+        word.extend_from_slice(&after);
+        let mut mask = 0;
+        for letter in self.rules.alphabet.letters() {
+            word[before.len()] = letter;
+            if self.dictionary.contains(&word) {
+                mask |= letter_bit(letter);
+            }
+        }
+        mask
+    }
+
+    fn left_part(&mut self, left: &mut Vec<Tile>, node: u32, limit: u32) {
+        let start = self.anchor - left.len() as u32;
+        self.placed = left
+            .iter()
+            .enumerate()
+            .map(|(i, tile)| {
+                let (x, y) = self.square(start + i as u32);
+                Placement::new(x, y, *tile)
+            })
+            .collect();
+        self.extend_right(node, self.anchor, left.len() as u32);
+        if limit == 0 {
+            return;
+        }
+        let dictionary = self.dictionary;
+        for (letter, child) in dictionary.children(node) {
+            if self.rack.take(*letter) {
+                left.push(Tile::new(*letter));
+                self.left_part(left, *child, limit - 1);
+                left.pop();
+                self.rack.put_back(*letter);
+            }
+            if self.rack.blanks > 0 {
+                self.rack.blanks -= 1;
+                left.push(Tile::blank(*letter));
+                self.left_part(left, *child, limit - 1);
+                left.pop();
+                self.rack.blanks += 1;
+            }
+        }
+    }
+
+    fn extend_right(&mut self, node: u32, position: u32, length: u32) {
+        let dictionary = self.dictionary;
+        if position < self.line_length() {
+            if let Some(tile) = self.tile(position) {
+                if let Some(child) = dictionary.child(node, tile.letter) {
+                    self.extend_right(child, position + 1, length + 1);
+                }
+                return;
+            }
+        }
+        if position > self.anchor && length >= 2 && dictionary.is_terminal(node) {
+            self.record();
+        }
+        if position >= self.line_length() {
+            return;
+        }
+        let allowed = self.cross_checks[position as usize];
+        let (x, y) = self.square(position);
+        for (letter, child) in dictionary.children(node) {
+            if allowed & letter_bit(*letter) == 0 {
+                continue;
+            }
+            if self.rack.take(*letter) {
+                self.placed.push(Placement::new(x, y, Tile::new(*letter)));
+                self.extend_right(*child, position + 1, length + 1);
+                self.placed.pop();
+                self.rack.put_back(*letter);
+            }
+            if self.rack.blanks > 0 {
+                self.rack.blanks -= 1;
+                self.placed.push(Placement::new(x, y, Tile::blank(*letter)));
+                self.extend_right(*child, position + 1, length + 1);
+                self.placed.pop();
+                self.rack.blanks += 1;
+            }
+        }
+    }
+
+    fn record(&mut self) {
+        if self.placed.len() == 1 && !self.single_tiles.insert(self.placed[0]) {
+            return;
+        }
+        let score = calculate_score_from_placement(self.board, self.rules, &self.placed);
+        self.plays.push(Play {
+            placements: self.placed.clone(),
+            score,
+        });
+    }
+}
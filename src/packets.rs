@@ -1,3 +1,5 @@
+use crate::alphabet::Letter;
+use crate::game::{Placement, Tile};
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use std::io::prelude::*;
 use std::io::Cursor;
@@ -60,6 +62,22 @@ impl PacketTo for u32 {
     }
 }
 
+impl PacketFrom for i32 {
+    fn decode(input: &mut Cursor<&[u8]>) -> Self {
+        input.read_i32::<BigEndian>().unwrap()
+    }
+}
+
+impl PacketTo for i32 {
+    fn length(self) -> usize {
+        std::mem::size_of::<i32>()
+    }
+    fn encode<T: Write>(self, writer: &mut T) -> IoResult<()> {
+        writer.write_i32::<BigEndian>(self).unwrap();
+        Ok(())
+    }
+}
+
 impl PacketFrom for u64 {
     fn decode(input: &mut Cursor<&[u8]>) -> Self {
         input.read_u64::<BigEndian>().unwrap()
@@ -76,20 +94,52 @@ impl PacketTo for u64 {
     }
 }
 
+impl PacketFrom for bool {
+    fn decode(input: &mut Cursor<&[u8]>) -> Self {
+        input.read_u8().unwrap() != 0
+    }
+}
+
+impl PacketTo for bool {
+    fn length(self) -> usize {
+        std::mem::size_of::<u8>()
+    }
+    fn encode<T: Write>(self, writer: &mut T) -> IoResult<()> {
+        writer.write_u8(self as u8).unwrap();
+        Ok(())
+    }
+}
+
 impl PacketFrom for char {
     fn decode(input: &mut Cursor<&[u8]>) -> Self {
-        input.read_u8().unwrap() as char
+        char::from_u32(input.read_u32::<BigEndian>().unwrap())
+            .unwrap_or(char::REPLACEMENT_CHARACTER)
     }
 }
 
 impl PacketTo for char {
     fn length(self) -> usize {
-        // Rust actually stores characters as u16
-        // Since unicode characters can be 2 bytes, but we only care about ASCII letters
+        // Sent as the full code point so letters outside ASCII survive
+        std::mem::size_of::<u32>()
+    }
+    fn encode<T: Write>(self, writer: &mut T) -> IoResult<()> {
+        writer.write_u32::<BigEndian>(self as u32).unwrap();
+        Ok(())
+    }
+}
+
+impl PacketFrom for Letter {
+    fn decode(input: &mut Cursor<&[u8]>) -> Self {
+        Letter::new(input.read_u8().unwrap())
+    }
+}
+
+impl PacketTo for Letter {
+    fn length(self) -> usize {
         std::mem::size_of::<u8>()
     }
     fn encode<T: Write>(self, writer: &mut T) -> IoResult<()> {
-        writer.write_u8(self as u8).unwrap();
+        writer.write_u8(self.index()).unwrap();
         Ok(())
     }
 }
@@ -97,7 +147,7 @@ impl PacketTo for char {
 impl PacketFrom for String {
     fn decode(input: &mut Cursor<&[u8]>) -> Self {
         let length = input.read_u16::<BigEndian>().unwrap();
-        let mut buffer: Vec<u8> = Vec::with_capacity(length as usize);
+        let mut buffer: Vec<u8> = vec![0u8; length as usize];
         let bytes_read = input.read_exact(&mut buffer);
         assert!(bytes_read.is_ok());
         String::from_utf8(buffer).unwrap()
@@ -106,13 +156,14 @@ impl PacketFrom for String {
 
 impl PacketTo for String {
     fn length(self) -> usize {
-        std::mem::size_of::<char>() * self.len()
+        // Strings are sent as UTF-8 so the length is in bytes, not characters
+        self.len()
     }
 
     fn encode<T: Write>(self, writer: &mut T) -> IoResult<()> {
         let length = self.clone().length() as u16;
         length.encode(writer)?;
-        writer.write(self.as_bytes()).unwrap();
+        writer.write_all(self.as_bytes()).unwrap();
         Ok(())
     }
 }
@@ -164,18 +215,17 @@ impl PacketTo for AckState {
     }
 }
 
-impl PacketFrom for Vec<char> {
+impl PacketFrom for Vec<Letter> {
     fn decode(input: &mut Cursor<&[u8]>) -> Self {
         let length = input.read_u16::<BigEndian>().unwrap() as usize;
         let mut buffer: Vec<u8> = vec![0u8; length];
-        let bytes_read = input.read(&mut buffer);
+        let bytes_read = input.read_exact(&mut buffer);
         assert!(bytes_read.is_ok());
-        let content = buffer.iter().map(|b| *b as char).collect::<Vec<char>>();
-        content
+        buffer.into_iter().map(Letter::new).collect()
     }
 }
 
-impl PacketTo for Vec<char> {
+impl PacketTo for Vec<Letter> {
     fn length(self) -> usize {
         std::mem::size_of::<u8>() * self.len()
     }
@@ -183,10 +233,9 @@ impl PacketTo for Vec<char> {
         let length = self.clone().length() as u16;
         length.encode(writer)?;
         writer
-            .write(
-                self.as_slice()
-                    .iter()
-                    .map(|c| *c as u8)
+            .write_all(
+                self.iter()
+                    .map(|letter| letter.index())
                     .collect::<Vec<u8>>()
                     .as_slice(),
             )
@@ -203,6 +252,7 @@ macro_rules! dec_packet {
         }
 
         impl $name {
+            #[allow(clippy::too_many_arguments)]
             pub fn new($($v: $t),*) -> $name {
                 Self {
                     $(
@@ -210,6 +260,12 @@ macro_rules! dec_packet {
                     ),*
                 }
             }
+
+            $(
+            pub fn $v(&self) -> &$t {
+                &self.$v
+            }
+            )*
         }
 
         impl PacketFrom for $name {
@@ -282,9 +338,34 @@ macro_rules! dec_packets {
     }
 }
 
+// Teams are numbered from 1, a team of 0 means the game has no teams or, when joining, that any
+// team will do
 dec_packets!(
-    0:Ack {id: u16, state: AckState};
-    1:PlayerState {id: u16, player: u8, username: String, tiles: Vec<char>, score: u8};
-    2:Place {id: u16, tile: char, x: u32, y: u32};
-    3:GameState {id: u16, placed: Vec<char>};
+    0:Ack {id: u16, state: AckState, reason: String};
+    1:PlayerState {id: u16, player: u8, identity: u64, username: String, team: u8, tiles: Vec<Letter>, score: i32};
+    2:Place {id: u16, tile: Letter, blank: bool, x: u32, y: u32};
+    3:GameState {id: u16, placed: Vec<Letter>};
+    4:Pass {id: u16, player: u8};
+    5:Exchange {id: u16, player: u8, tiles: Vec<Letter>};
+    6:FinalStanding {id: u16, player: u8, username: String, team: u8, score: i32, adjustment: i32, time_penalty: u32, rank: u8};
+    7:Challenge {id: u16, player: u8, challenge: bool};
+    8:ChallengeResult {id: u16, player: u8, challenger: u8, phony: bool, words: String};
+    9:HintRequest {id: u16, player: u8, count: u8};
+    10:Hint {id: u16, player: u8, rank: u8, word: String, x: u32, y: u32, across: bool, score: u32};
+    11:AlphabetLetter {id: u16, letter: Letter, display: String, score: u32};
+    12:Undo {id: u16, player: u8};
+    13:Join {id: u16, identity: u64, username: String, team: u8};
+    14:ClockState {id: u16, player: u8, remaining: u32, overtime: u32, running: bool};
+    15:Spectate {id: u16};
 );
+
+impl From<&Place> for Placement {
+    fn from(place: &Place) -> Self {
+        let tile = if place.blank {
+            Tile::blank(place.tile)
+        } else {
+            Tile::new(place.tile)
+        };
+        Placement::new(place.x, place.y, tile)
+    }
+}
@@ -0,0 +1,282 @@
+use crate::game::GameState;
+use crate::identity::PlayerId;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Elo ratings. Games with more than two sides are split into a result between every pair of
+// players on different sides, each pair counting for an equal share of the K factor.
+pub const INITIAL_RATING: f64 = 1500.0;
+pub const K_FACTOR: f64 = 32.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rating {
+    pub id: PlayerId,
+    // The name the player last played under
+    pub name: String,
+    pub rating: f64,
+    pub games: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RatingChange {
+    // Games are numbered from 1 in the order they were rated
+    pub game: u32,
+    pub id: PlayerId,
+    pub name: String,
+    pub before: f64,
+    pub after: f64,
+}
+
+// How one player finished a game
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placing {
+    pub id: PlayerId,
+    pub name: String,
+    pub rank: u8,
+    // Partners aren't rated against each other
+    pub team: Option<usize>,
+}
+
+#[derive(Debug)]
+pub enum RatingsError {
+    Io(io::Error),
+    Parse { line: usize, reason: String },
+    Unfinished,
+    TooFewPlayers,
+}
+
+impl RatingsError {
+    fn parse(line: usize, reason: impl Into<String>) -> Self {
+        RatingsError::Parse {
+            line,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for RatingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RatingsError::Io(e) => write!(f, "Couldn't read the ratings: {}", e),
+            RatingsError::Parse { line, reason } => write!(f, "Line {}: {}", line, reason),
+            RatingsError::Unfinished => write!(f, "Only finished games can be rated"),
+            RatingsError::TooFewPlayers => write!(f, "Rated games need players on two sides"),
+        }
+    }
+}
+
+impl std::error::Error for RatingsError {}
+
+// The ratings are worked out from the history, which is all that is kept in the file
+#[derive(Debug, Clone, Default)]
+pub struct Ratings {
+    ratings: HashMap<PlayerId, Rating>,
+    history: Vec<RatingChange>,
+    games: u32,
+}
+
+impl Ratings {
+    pub fn new() -> Self {
+        Ratings::default()
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, RatingsError> {
+        let contents = fs::read_to_string(path).map_err(RatingsError::Io)?;
+        Ratings::parse(&contents)
+    }
+
+    // Writes to a temporary file first so a crash part way through can't lose the history
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, self.to_text())?;
+        fs::rename(&temporary, path)
+    }
+
+    // One change per line, `game player before after name`, where player is the id number.
+    // Blank lines and lines starting with '#' are skipped.
+    pub fn parse(text: &str) -> Result<Self, RatingsError> {
+        let mut ratings = Ratings::new();
+        for (index, line) in text.lines().enumerate() {
+            let number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parts: Vec<&str> = line.splitn(5, char::is_whitespace).collect();
+            let (game, id, before, after, name) = match parts.as_slice() {
+                [game, id, before, after, name] if !name.trim().is_empty() => {
+                    (*game, *id, *before, *after, name.trim())
+                }
+                _ => {
+                    return Err(RatingsError::parse(
+                        number,
+                        "Expected the game, player, rating before, rating after and name",
+                    ))
+                }
+            };
+            let game = game.parse::<u32>().map_err(|_| {
+                RatingsError::parse(number, format!("{:?} isn't a game number", game))
+            })?;
+            // Games have to be in the order they were rated
+            if game < ratings.games.max(1) {
+                return Err(RatingsError::parse(
+                    number,
+                    format!("Game {} is out of order", game),
+                ));
+            }
+            let id = id
+                .parse::<u64>()
+                .map_err(|_| RatingsError::parse(number, format!("{:?} isn't a player", id)))?;
+            let rating = |value: &str| {
+                value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|rating| rating.is_finite())
+                    .ok_or_else(|| {
+                        RatingsError::parse(number, format!("{:?} isn't a rating", value))
+                    })
+            };
+            ratings.apply(RatingChange {
+                game,
+                id: PlayerId::new(id),
+                name: name.to_string(),
+                before: rating(before)?,
+                after: rating(after)?,
+            });
+        }
+        Ok(ratings)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = "# game player before after name\n".to_string();
+        for change in &self.history {
+            text.push_str(&format!(
+                "{} {} {} {} {}\n",
+                change.game,
+                change.id.value(),
+                change.before,
+                change.after,
+                change.name
+            ));
+        }
+        text
+    }
+
+    // Players who haven't been rated yet start from the initial rating
+    pub fn rating(&self, id: PlayerId) -> f64 {
+        self.ratings.get(&id).map_or(INITIAL_RATING, |r| r.rating)
+    }
+
+    pub fn get(&self, id: PlayerId) -> Option<&Rating> {
+        self.ratings.get(&id)
+    }
+
+    // Highest rated first
+    pub fn leaderboard(&self) -> Vec<&Rating> {
+        let mut ratings: Vec<&Rating> = self.ratings.values().collect();
+        ratings.sort_by(|a, b| b.rating.total_cmp(&a.rating).then(a.id.cmp(&b.id)));
+        ratings
+    }
+
+    pub fn history(&self) -> &[RatingChange] {
+        &self.history
+    }
+
+    pub fn games(&self) -> u32 {
+        self.games
+    }
+
+    // Rates a finished game from its standings, returning the changes it made
+    pub fn record(&mut self, game: &GameState) -> Result<&[RatingChange], RatingsError> {
+        let standings = game.standings().ok_or(RatingsError::Unfinished)?;
+        let placings: Vec<Placing> = standings
+            .standings
+            .iter()
+            .filter_map(|standing| {
+                Some(Placing {
+                    id: game.player(standing.seat)?.id(),
+                    name: standing.username.clone(),
+                    rank: standing.rank,
+                    team: standing.team,
+                })
+            })
+            .collect();
+        self.record_placings(&placings)
+    }
+
+    // Every pair of players on different sides is scored as a win, loss or draw by rank. An
+    // identity in more than one seat, like a computer player, isn't rated against itself and
+    // gets the changes from all of its seats.
+    pub fn record_placings(
+        &mut self,
+        placings: &[Placing],
+    ) -> Result<&[RatingChange], RatingsError> {
+        let opponents = |index: usize| {
+            let placing = &placings[index];
+            (0..placings.len()).filter(move |other| {
+                let other = &placings[*other];
+                other.id != placing.id && (placing.team.is_none() || other.team != placing.team)
+            })
+        };
+        if (0..placings.len()).all(|index| opponents(index).next().is_none()) {
+            return Err(RatingsError::TooFewPlayers);
+        }
+
+        let mut deltas: Vec<(PlayerId, String, f64)> = vec![];
+        for (index, placing) in placings.iter().enumerate() {
+            let count = opponents(index).count();
+            let mut delta = 0.0;
+            for other in opponents(index) {
+                let actual = match placing.rank.cmp(&placings[other].rank) {
+                    std::cmp::Ordering::Less => 1.0,
+                    std::cmp::Ordering::Equal => 0.5,
+                    std::cmp::Ordering::Greater => 0.0,
+                };
+                let expected =
+                    expected_score(self.rating(placing.id), self.rating(placings[other].id));
+                delta += K_FACTOR / count as f64 * (actual - expected);
+            }
+            match deltas.iter_mut().find(|(id, _, _)| *id == placing.id) {
+                Some((_, _, total)) => *total += delta,
+                None => deltas.push((placing.id, placing.name.clone(), delta)),
+            }
+        }
+
+        let game = self.games + 1;
+        let start = self.history.len();
+        for (id, name, delta) in deltas {
+            let before = self.rating(id);
+            self.apply(RatingChange {
+                game,
+                id,
+                name,
+                before,
+                after: before + delta,
+            });
+        }
+        Ok(&self.history[start..])
+    }
+
+    fn apply(&mut self, change: RatingChange) {
+        let rating = self.ratings.entry(change.id).or_insert(Rating {
+            id: change.id,
+            name: change.name.clone(),
+            rating: INITIAL_RATING,
+            games: 0,
+        });
+        rating.name = change.name.clone();
+        rating.rating = change.after;
+        rating.games += 1;
+        self.games = self.games.max(change.game);
+        self.history.push(change);
+    }
+}
+
+// The share of the points a player is expected to take from a game against the other player
+pub fn expected_score(rating: f64, other: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((other - rating) / 400.0))
+}
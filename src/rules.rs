@@ -0,0 +1,393 @@
+use crate::alphabet::{Alphabet, Letter, MAX_LETTERS};
+use crate::game::{Premium, Tile};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const STANDARD: &str = include_str!("../rulesets/standard.txt");
+const SUPER: &str = include_str!("../rulesets/super.txt");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    pub name: String,
+    pub rack_size: usize,
+    pub players: usize,
+    // Awarded for using every tile in the hand in one play
+    pub bingo_bonus: u32,
+    // The tiles of the game's language along with how many of each are in the bag and what
+    // they score
+    pub alphabet: Alphabet,
+    // One string per row of the board, T/D/t/d/Q/q mark the triple, double and quadruple word
+    // and letter squares and * the centre
+    pub layout: Vec<String>,
+}
+
+impl Rules {
+    pub fn standard() -> Self {
+        Rules::parse(STANDARD).expect("The standard ruleset is invalid")
+    }
+
+    // A 21x21 board with 200 tiles
+    pub fn super_board() -> Self {
+        Rules::parse(SUPER).expect("The super ruleset is invalid")
+    }
+
+    pub fn preset(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "standard" => Some(Rules::standard()),
+            "super" => Some(Rules::super_board()),
+            _ => None,
+        }
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, RulesetError> {
+        let contents = fs::read_to_string(path).map_err(RulesetError::Io)?;
+        Rules::parse(&contents)
+    }
+
+    // Rulesets are plain text, see rulesets/standard.txt. Settings are `key = value` lines,
+    // followed by a [layout] section drawing the board and a [tiles] section listing each
+    // letter with its count and score. Blank lines and lines starting with '#' are skipped.
+    pub fn parse(text: &str) -> Result<Self, RulesetError> {
+        let mut parser = Parser::default();
+        for (index, line) in text.lines().enumerate() {
+            parser.lines = index + 1;
+            parser.line(index + 1, line.trim())?;
+        }
+        parser.finish()
+    }
+
+    // Writes the rules back out in the ruleset file format
+    pub fn to_ruleset(&self) -> String {
+        let mut text = format!(
+            "name = {}\nlanguage = {}\nrack size = {}\nplayers = {}\nbingo bonus = {}\n\
+             board = {}x{}\nblanks = {}\n\n[layout]\n",
+            self.name,
+            self.alphabet.name(),
+            self.rack_size,
+            self.players,
+            self.bingo_bonus,
+            self.board_width(),
+            self.board_height(),
+            self.alphabet.blanks()
+        );
+        for row in &self.layout {
+            text.push_str(row);
+            text.push('\n');
+        }
+        text.push_str("\n[tiles]\n");
+        for letter in self.alphabet.letters() {
+            text.push_str(&format!(
+                "{} {} {}\n",
+                self.alphabet.display(letter),
+                self.alphabet.count(letter),
+                self.alphabet.score(letter)
+            ));
+        }
+        text
+    }
+
+    pub fn letter_score(&self, letter: Letter) -> u32 {
+        self.alphabet.score(letter)
+    }
+
+    pub fn tile_score(&self, tile: Tile) -> u32 {
+        if tile.blank {
+            0
+        } else {
+            self.letter_score(tile.letter)
+        }
+    }
+
+    pub fn board_width(&self) -> u32 {
+        self.layout.first().map_or(0, |row| row.chars().count()) as u32
+    }
+
+    pub fn board_height(&self) -> u32 {
+        self.layout.len() as u32
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules::standard()
+    }
+}
+
+#[derive(Debug)]
+pub enum RulesetError {
+    Io(io::Error),
+    Parse { line: usize, reason: String },
+}
+
+impl RulesetError {
+    fn parse(line: usize, reason: impl Into<String>) -> Self {
+        RulesetError::Parse {
+            line,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for RulesetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RulesetError::Io(e) => write!(f, "Couldn't read the ruleset: {}", e),
+            RulesetError::Parse { line, reason } => write!(f, "Line {}: {}", line, reason),
+        }
+    }
+}
+
+impl std::error::Error for RulesetError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Section {
+    #[default]
+    Settings,
+    Layout,
+    Tiles,
+}
+
+// Values are kept with the line they came from so later checks can point at it
+#[derive(Default)]
+struct Parser {
+    lines: usize,
+    section: Section,
+    name: Option<String>,
+    language: Option<(usize, String)>,
+    rack_size: Option<usize>,
+    players: Option<usize>,
+    bingo_bonus: Option<u32>,
+    blanks: Option<u32>,
+    board: Option<(usize, u32, u32)>,
+    layout_line: Option<usize>,
+    layout: Vec<String>,
+    tiles_line: Option<usize>,
+    tiles: Vec<(String, u32, u32)>,
+}
+
+impl Parser {
+    fn line(&mut self, number: usize, line: &str) -> Result<(), RulesetError> {
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(());
+        }
+        if line.starts_with('[') {
+            return self.section(number, line);
+        }
+        match self.section {
+            Section::Settings => self.setting(number, line),
+            Section::Layout => self.layout_row(number, line),
+            Section::Tiles => self.tile(number, line),
+        }
+    }
+
+    fn section(&mut self, number: usize, line: &str) -> Result<(), RulesetError> {
+        let (section, seen) = match line {
+            "[layout]" => (Section::Layout, &mut self.layout_line),
+            "[tiles]" => (Section::Tiles, &mut self.tiles_line),
+            _ => {
+                return Err(RulesetError::parse(
+                    number,
+                    format!("Unknown section {}", line),
+                ))
+            }
+        };
+        if seen.is_some() {
+            return Err(RulesetError::parse(
+                number,
+                format!("{} appears twice", line),
+            ));
+        }
+        *seen = Some(number);
+        self.section = section;
+        Ok(())
+    }
+
+    fn setting(&mut self, number: usize, line: &str) -> Result<(), RulesetError> {
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| RulesetError::parse(number, "Expected a `key = value` setting"))?;
+        let (key, value) = (key.trim(), value.trim());
+        match key {
+            "name" => self.name = Some(value.to_string()),
+            "language" => self.language = Some((number, value.to_string())),
+            "rack size" => self.rack_size = Some(number_in(number, key, value, 1)?),
+            "players" => {
+                let players = number_in(number, key, value, 1)?;
+                if players > u8::MAX as usize {
+                    return Err(RulesetError::parse(
+                        number,
+                        format!("There can be at most {} players", u8::MAX),
+                    ));
+                }
+                self.players = Some(players);
+            }
+            "bingo bonus" => self.bingo_bonus = Some(number_in(number, key, value, 0)?),
+            "blanks" => self.blanks = Some(number_in(number, key, value, 0)?),
+            "board" => {
+                let (width, height) = value
+                    .split_once('x')
+                    .ok_or_else(|| RulesetError::parse(number, "board should look like 15x15"))?;
+                let width = number_in(number, key, width.trim(), 1)?;
+                let height = number_in(number, key, height.trim(), 1)?;
+                self.board = Some((number, width, height));
+            }
+            _ => {
+                return Err(RulesetError::parse(
+                    number,
+                    format!("Unknown setting {:?}", key),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    fn layout_row(&mut self, number: usize, line: &str) -> Result<(), RulesetError> {
+        if let Some(symbol) = line.chars().find(|c| Premium::from_layout(*c).is_none()) {
+            return Err(RulesetError::parse(
+                number,
+                format!("Unknown layout symbol {:?}", symbol),
+            ));
+        }
+        if let Some(first) = self.layout.first() {
+            if first.chars().count() != line.chars().count() {
+                return Err(RulesetError::parse(
+                    number,
+                    format!(
+                        "Row is {} squares wide but the first row is {}",
+                        line.chars().count(),
+                        first.chars().count()
+                    ),
+                ));
+            }
+        }
+        let centres = self
+            .layout
+            .iter()
+            .map(|row| row.as_str())
+            .chain([line])
+            .map(|row| row.matches('*').count())
+            .sum::<usize>();
+        if centres > 1 {
+            return Err(RulesetError::parse(
+                number,
+                "The board has more than one centre",
+            ));
+        }
+        self.layout.push(line.to_string());
+        Ok(())
+    }
+
+    fn tile(&mut self, number: usize, line: &str) -> Result<(), RulesetError> {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let (display, count, score) = match parts.as_slice() {
+            [display, count, score] => (
+                display.to_uppercase(),
+                number_in(number, "count", count, 0)?,
+                number_in(number, "score", score, 0)?,
+            ),
+            _ => {
+                return Err(RulesetError::parse(
+                    number,
+                    "Expected a letter, how many are in the bag and its score",
+                ))
+            }
+        };
+        if display == "?" {
+            return Err(RulesetError::parse(
+                number,
+                "Blanks are set with the blanks setting",
+            ));
+        }
+        if self.tiles.iter().any(|(d, _, _)| *d == display) {
+            return Err(RulesetError::parse(
+                number,
+                format!("{} is listed twice", display),
+            ));
+        }
+        if self.tiles.len() >= MAX_LETTERS {
+            return Err(RulesetError::parse(
+                number,
+                format!("There can be at most {} letters", MAX_LETTERS),
+            ));
+        }
+        self.tiles.push((display, count, score));
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Rules, RulesetError> {
+        let layout_line = self
+            .layout_line
+            .ok_or_else(|| RulesetError::parse(self.lines, "There is no [layout] section"))?;
+        if self.layout.is_empty() {
+            return Err(RulesetError::parse(layout_line, "The layout has no rows"));
+        }
+        let layout = self.layout;
+        if let Some((number, width, height)) = self.board {
+            let rows = layout.len() as u32;
+            let columns = layout[0].chars().count() as u32;
+            if (width, height) != (columns, rows) {
+                return Err(RulesetError::parse(
+                    number,
+                    format!(
+                        "The board is {}x{} but the layout is {}x{}",
+                        width, height, columns, rows
+                    ),
+                ));
+            }
+        }
+
+        let (language_line, language) = match self.language {
+            Some((number, language)) => (number, language),
+            None => (self.lines, "english".to_string()),
+        };
+        let mut alphabet = match self.tiles_line {
+            Some(tiles_line) => {
+                if self.tiles.is_empty() {
+                    return Err(RulesetError::parse(tiles_line, "There are no tiles"));
+                }
+                let mut alphabet = Alphabet::new(&language, 2);
+                for (display, count, score) in &self.tiles {
+                    alphabet.add_letter(display, *count, *score);
+                }
+                alphabet
+            }
+            None => Alphabet::by_name(&language).ok_or_else(|| {
+                RulesetError::parse(
+                    language_line,
+                    format!("There is no built in alphabet for {:?}", language),
+                )
+            })?,
+        };
+        if let Some(blanks) = self.blanks {
+            alphabet.set_blanks(blanks);
+        }
+
+        Ok(Rules {
+            name: self.name.unwrap_or_else(|| "custom".to_string()),
+            rack_size: self.rack_size.unwrap_or(7),
+            players: self.players.unwrap_or(4),
+            bingo_bonus: self.bingo_bonus.unwrap_or(50),
+            alphabet,
+            layout,
+        })
+    }
+}
+
+fn number_in<T>(line: usize, key: &str, value: &str, min: T) -> Result<T, RulesetError>
+where
+    T: std::str::FromStr + PartialOrd + fmt::Display,
+{
+    match value.parse::<T>() {
+        Ok(number) if number >= min => Ok(number),
+        Ok(_) => Err(RulesetError::parse(
+            line,
+            format!("{} must be at least {}", key, min),
+        )),
+        Err(_) => Err(RulesetError::parse(
+            line,
+            format!("{} should be a number, not {:?}", key, value),
+        )),
+    }
+}
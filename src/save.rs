@@ -0,0 +1,638 @@
+use crate::ai::Difficulty;
+use crate::alphabet::Letter;
+use crate::clock::{Clock, TimeControl};
+use crate::game::{
+    Bag, ChallengeRule, Direction, EndReason, GameState, PendingPlay, Placement, Player, Score,
+    Standing, Standings, Tile, Variant, WordScore, TEAMS,
+};
+use crate::history::{Move, MoveKind, TurnState};
+use crate::identity::{Identity, PlayerId};
+use crate::rng::SeededRng;
+use crate::rules::{Rules, RulesetError};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+const MAGIC: &[u8; 4] = b"WRDS";
+// Bumped whenever the layout of a save changes, saves from other versions are refused rather
+// than misread
+pub const SAVE_VERSION: u16 = 6;
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    NotASave,
+    UnsupportedVersion(u16),
+    Rules(RulesetError),
+    Corrupt(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "Couldn't read the save: {}", e),
+            SaveError::NotASave => write!(f, "This isn't a saved game"),
+            SaveError::UnsupportedVersion(version) => write!(
+                f,
+                "The save is from version {} but only version {} saves can be loaded",
+                version, SAVE_VERSION
+            ),
+            SaveError::Rules(e) => write!(f, "The save's rules are invalid: {}", e),
+            SaveError::Corrupt(reason) => write!(f, "The save is damaged: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(e: io::Error) -> Self {
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            SaveError::Corrupt("it ends part way through".to_string())
+        } else {
+            SaveError::Io(e)
+        }
+    }
+}
+
+// Saves hold everything needed to carry on the game except the word lists, which have to be
+// set again after loading
+impl GameState {
+    // Writes to a temporary file first so a crash part way through can't ruin the last save
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let temporary = path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&temporary)?);
+        self.save(&mut writer)?;
+        writer.flush()?;
+        drop(writer);
+        fs::rename(&temporary, path)
+    }
+
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, SaveError> {
+        let file = File::open(path).map_err(SaveError::Io)?;
+        GameState::load(&mut BufReader::new(file))
+    }
+
+    pub fn save<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_u16::<BigEndian>(SAVE_VERSION)?;
+        write_string(writer, &self.rules.to_ruleset())?;
+        writer.write_u64::<BigEndian>(self.id as u64)?;
+        writer.write_u64::<BigEndian>(self.seed)?;
+        writer.write_u8(match self.challenge_rule {
+            ChallengeRule::Void => 0,
+            ChallengeRule::Single => 1,
+            ChallengeRule::Double => 2,
+        })?;
+        writer.write_u8(match self.variant {
+            Variant::Standard => 0,
+            Variant::Clabbers => 1,
+        })?;
+        let on_board: Vec<Placement> = self
+            .board
+            .tiles()
+            .map(|(x, y, tile)| Placement::new(x, y, tile))
+            .collect();
+        write_placements(writer, &on_board)?;
+        writer.write_u16::<BigEndian>(self.players.len() as u16)?;
+        for player in &self.players {
+            write_player(writer, player.as_ref())?;
+        }
+        match self.time_control {
+            Some(time_control) => {
+                writer.write_u8(1)?;
+                write_duration(writer, time_control.initial)?;
+                write_duration(writer, time_control.increment)?;
+                for clock in &self.clocks {
+                    write_duration(writer, clock.remaining)?;
+                    write_duration(writer, clock.overtime)?;
+                }
+            }
+            None => writer.write_u8(0)?,
+        }
+        writer.write_u8(self.team_play as u8)?;
+        write_turn_state(writer, &self.snapshot())?;
+        writer.write_u32::<BigEndian>(self.history.len() as u32)?;
+        for entry in &self.history {
+            write_move(writer, entry)?;
+        }
+        Ok(())
+    }
+
+    pub fn load<R: Read>(reader: &mut R) -> Result<Self, SaveError> {
+        let mut magic = [0u8; 4];
+        reader
+            .read_exact(&mut magic)
+            .map_err(|_| SaveError::NotASave)?;
+        if &magic != MAGIC {
+            return Err(SaveError::NotASave);
+        }
+        let version = reader.read_u16::<BigEndian>()?;
+        if version != SAVE_VERSION {
+            return Err(SaveError::UnsupportedVersion(version));
+        }
+        let rules = Rules::parse(&read_string(reader)?).map_err(SaveError::Rules)?;
+        let id = reader.read_u64::<BigEndian>()? as usize;
+        let seed = reader.read_u64::<BigEndian>()?;
+        let mut game = GameState::new(id, rules, Some(seed));
+        game.challenge_rule = match reader.read_u8()? {
+            0 => ChallengeRule::Void,
+            1 => ChallengeRule::Single,
+            2 => ChallengeRule::Double,
+            rule => return Err(corrupt(format!("unknown challenge rule {}", rule))),
+        };
+        game.variant = match reader.read_u8()? {
+            0 => Variant::Standard,
+            1 => Variant::Clabbers,
+            variant => return Err(corrupt(format!("unknown variant {}", variant))),
+        };
+
+        let mut loader = Loader {
+            reader,
+            game: &game,
+        };
+        let on_board = loader.placements()?;
+        let seats = loader.reader.read_u16::<BigEndian>()? as usize;
+        if seats != game.rules.players {
+            return Err(corrupt(format!(
+                "it has {} seats but the rules have {}",
+                seats, game.rules.players
+            )));
+        }
+        let mut players = Vec::with_capacity(seats);
+        for _ in 0..seats {
+            players.push(loader.player()?);
+        }
+        let (time_control, clocks) = if loader.flag()? {
+            let time_control =
+                TimeControl::new(read_duration(loader.reader)?, read_duration(loader.reader)?);
+            let mut clocks = vec![];
+            for _ in 0..seats {
+                clocks.push(Clock {
+                    remaining: read_duration(loader.reader)?,
+                    overtime: read_duration(loader.reader)?,
+                });
+            }
+            (Some(time_control), clocks)
+        } else {
+            (None, vec![])
+        };
+        let team_play = loader.flag()?;
+        if team_play && !seats.is_multiple_of(TEAMS) {
+            return Err(corrupt(format!(
+                "{} seats can't be split into teams",
+                seats
+            )));
+        }
+        let state = loader.turn_state()?;
+        let moves = loader.reader.read_u32::<BigEndian>()?;
+        let mut history = vec![];
+        for _ in 0..moves {
+            history.push(loader.history_move()?);
+        }
+
+        for placement in on_board {
+            if !game.board.place(placement.x, placement.y, placement.tile) {
+                return Err(corrupt(format!(
+                    "there are two tiles on {},{}",
+                    placement.x, placement.y
+                )));
+            }
+        }
+        game.players = players;
+        game.time_control = time_control;
+        game.clocks = clocks;
+        game.team_play = team_play;
+        game.history = history;
+        game.bag = state.bag;
+        game.turn = state.turn;
+        game.scoreless_turns = state.scoreless_turns;
+        game.pending = state.pending;
+        game.forfeits = state.forfeits;
+        game.standings = state.standings;
+        // Every tile has to be somewhere exactly once, or the game can't be played on
+        game.audit().map_err(|e| corrupt(e.to_string()))?;
+        Ok(game)
+    }
+}
+
+fn corrupt(reason: String) -> SaveError {
+    SaveError::Corrupt(reason)
+}
+
+fn write_string<W: Write>(writer: &mut W, text: &str) -> io::Result<()> {
+    writer.write_u32::<BigEndian>(text.len() as u32)?;
+    writer.write_all(text.as_bytes())
+}
+
+// Kept to the millisecond
+fn write_duration<W: Write>(writer: &mut W, duration: Duration) -> io::Result<()> {
+    writer.write_u64::<BigEndian>(duration.as_millis().min(u64::MAX as u128) as u64)
+}
+
+fn write_letters<W: Write>(writer: &mut W, letters: &[Letter]) -> io::Result<()> {
+    writer.write_u16::<BigEndian>(letters.len() as u16)?;
+    for letter in letters {
+        writer.write_u8(letter.index())?;
+    }
+    Ok(())
+}
+
+fn write_placements<W: Write>(writer: &mut W, placements: &[Placement]) -> io::Result<()> {
+    writer.write_u16::<BigEndian>(placements.len() as u16)?;
+    for placement in placements {
+        writer.write_u32::<BigEndian>(placement.x)?;
+        writer.write_u32::<BigEndian>(placement.y)?;
+        writer.write_u8(placement.tile.letter.index())?;
+        writer.write_u8(placement.tile.blank as u8)?;
+    }
+    Ok(())
+}
+
+fn write_score<W: Write>(writer: &mut W, score: &Score) -> io::Result<()> {
+    writer.write_u16::<BigEndian>(score.words.len() as u16)?;
+    for word in &score.words {
+        write_string(writer, &word.word)?;
+        write_letters(writer, &word.letters)?;
+        writer.write_u32::<BigEndian>(word.x)?;
+        writer.write_u32::<BigEndian>(word.y)?;
+        writer.write_u8(match word.direction {
+            Direction::Across => 0,
+            Direction::Down => 1,
+        })?;
+        writer.write_u32::<BigEndian>(word.score)?;
+    }
+    writer.write_u32::<BigEndian>(score.bingo)?;
+    writer.write_u32::<BigEndian>(score.total)
+}
+
+fn write_player<W: Write>(writer: &mut W, player: Option<&Player>) -> io::Result<()> {
+    let player = match player {
+        Some(player) => player,
+        None => return writer.write_u8(0),
+    };
+    writer.write_u8(1)?;
+    writer.write_u64::<BigEndian>(player.id().value())?;
+    write_string(writer, player.username())?;
+    writer.write_i32::<BigEndian>(player.score)?;
+    write_letters(writer, &player.hand)?;
+    writer.write_u8(match player.computer {
+        None => 0,
+        Some(Difficulty::Beginner) => 1,
+        Some(Difficulty::Intermediate) => 2,
+        Some(Difficulty::Expert) => 3,
+    })
+}
+
+fn write_turn_state<W: Write>(writer: &mut W, state: &TurnState) -> io::Result<()> {
+    write_letters(writer, &state.bag.tiles)?;
+    writer.write_u64::<BigEndian>(state.bag.rng.state())?;
+    writer.write_u8(state.turn)?;
+    writer.write_u8(state.scoreless_turns)?;
+    match &state.pending {
+        Some(pending) => {
+            writer.write_u8(1)?;
+            writer.write_u8(pending.player as u8)?;
+            write_placements(writer, &pending.placements)?;
+            write_score(writer, &pending.score)?;
+            writer.write_u8(pending.went_out as u8)?;
+        }
+        None => writer.write_u8(0)?,
+    }
+    writer.write_u16::<BigEndian>(state.forfeits.len() as u16)?;
+    for forfeit in &state.forfeits {
+        writer.write_u8(*forfeit as u8)?;
+    }
+    match &state.standings {
+        Some(standings) => {
+            writer.write_u8(1)?;
+            match standings.reason {
+                EndReason::WentOut(seat) => {
+                    writer.write_u8(0)?;
+                    writer.write_u8(seat as u8)?;
+                }
+                EndReason::Scoreless => writer.write_u8(1)?,
+            }
+            writer.write_u16::<BigEndian>(standings.standings.len() as u16)?;
+            for standing in &standings.standings {
+                writer.write_u8(standing.seat as u8)?;
+                write_string(writer, &standing.username)?;
+                writer.write_i32::<BigEndian>(standing.score)?;
+                writer.write_i32::<BigEndian>(standing.adjustment)?;
+                writer.write_u32::<BigEndian>(standing.time_penalty)?;
+                match standing.team {
+                    Some(team) => {
+                        writer.write_u8(1)?;
+                        writer.write_u8(team as u8)?;
+                    }
+                    None => writer.write_u8(0)?,
+                }
+                writer.write_u8(standing.rank)?;
+            }
+        }
+        None => writer.write_u8(0)?,
+    }
+    Ok(())
+}
+
+fn write_move<W: Write>(writer: &mut W, entry: &Move) -> io::Result<()> {
+    writer.write_u8(entry.player as u8)?;
+    write_letters(writer, &entry.rack)?;
+    match &entry.kind {
+        MoveKind::Play { placements, score } => {
+            writer.write_u8(0)?;
+            write_placements(writer, placements)?;
+            write_score(writer, score)?;
+        }
+        MoveKind::Pass => writer.write_u8(1)?,
+        MoveKind::Exchange { tiles } => {
+            writer.write_u8(2)?;
+            write_letters(writer, tiles)?;
+        }
+        MoveKind::PhonyRemoved {
+            challenger,
+            placements,
+            words,
+        } => {
+            writer.write_u8(3)?;
+            writer.write_u8(*challenger as u8)?;
+            write_placements(writer, placements)?;
+            writer.write_u16::<BigEndian>(words.len() as u16)?;
+            for word in words {
+                write_string(writer, word)?;
+            }
+        }
+        MoveKind::ChallengeFailed {
+            challenged,
+            forfeit,
+        } => {
+            writer.write_u8(4)?;
+            writer.write_u8(*challenged as u8)?;
+            writer.write_u8(*forfeit as u8)?;
+        }
+        MoveKind::LostTurn => writer.write_u8(5)?,
+        MoveKind::EndRack { adjustment } => {
+            writer.write_u8(6)?;
+            writer.write_i32::<BigEndian>(*adjustment)?;
+        }
+        MoveKind::TimePenalty { points } => {
+            writer.write_u8(7)?;
+            writer.write_u32::<BigEndian>(*points)?;
+        }
+    }
+    writer.write_i32::<BigEndian>(entry.score_before)?;
+    writer.write_i32::<BigEndian>(entry.score_after)?;
+    write_turn_state(writer, &entry.state)
+}
+
+fn read_duration<R: Read>(reader: &mut R) -> Result<Duration, SaveError> {
+    Ok(Duration::from_millis(reader.read_u64::<BigEndian>()?))
+}
+
+fn read_string<R: Read>(reader: &mut R) -> Result<String, SaveError> {
+    let length = reader.read_u32::<BigEndian>()? as usize;
+    let mut buffer = vec![];
+    reader.take(length as u64).read_to_end(&mut buffer)?;
+    if buffer.len() != length {
+        return Err(corrupt("it ends part way through".to_string()));
+    }
+    String::from_utf8(buffer).map_err(|_| corrupt("a name isn't valid UTF-8".to_string()))
+}
+
+// Reads the parts of a save that depend on the rules, checking that letters, squares and seats
+// all exist in the game so that nothing loaded can cause a panic later on
+struct Loader<'a, R: Read> {
+    reader: &'a mut R,
+    game: &'a GameState,
+}
+
+impl<'a, R: Read> Loader<'a, R> {
+    fn flag(&mut self) -> Result<bool, SaveError> {
+        match self.reader.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            value => Err(corrupt(format!("{} should be 0 or 1", value))),
+        }
+    }
+
+    fn seat(&mut self) -> Result<usize, SaveError> {
+        let seat = self.reader.read_u8()? as usize;
+        if seat < self.game.rules.players {
+            Ok(seat)
+        } else {
+            Err(corrupt(format!("there is no seat {}", seat)))
+        }
+    }
+
+    fn team(&mut self) -> Result<usize, SaveError> {
+        let team = self.reader.read_u8()? as usize;
+        if team < TEAMS {
+            Ok(team)
+        } else {
+            Err(corrupt(format!("there is no team {}", team)))
+        }
+    }
+
+    fn string(&mut self) -> Result<String, SaveError> {
+        read_string(self.reader)
+    }
+
+    // Blanks are allowed in racks and the bag but not as a placed tile's letter
+    fn letter(&mut self, allow_blank: bool) -> Result<Letter, SaveError> {
+        let letter = Letter::new(self.reader.read_u8()?);
+        if self.game.rules.alphabet.contains(letter) || (allow_blank && letter.is_blank()) {
+            Ok(letter)
+        } else {
+            Err(corrupt(format!(
+                "tile {} isn't in the {} alphabet",
+                letter.index(),
+                self.game.rules.alphabet.name()
+            )))
+        }
+    }
+
+    fn letters(&mut self) -> Result<Vec<Letter>, SaveError> {
+        let length = self.reader.read_u16::<BigEndian>()?;
+        (0..length).map(|_| self.letter(true)).collect()
+    }
+
+    fn placements(&mut self) -> Result<Vec<Placement>, SaveError> {
+        let length = self.reader.read_u16::<BigEndian>()?;
+        let mut placements = vec![];
+        for _ in 0..length {
+            let x = self.reader.read_u32::<BigEndian>()?;
+            let y = self.reader.read_u32::<BigEndian>()?;
+            if !self.game.board.in_bounds(x, y) {
+                return Err(corrupt(format!("square {},{} is off the board", x, y)));
+            }
+            let letter = self.letter(false)?;
+            let tile = if self.flag()? {
+                Tile::blank(letter)
+            } else {
+                Tile::new(letter)
+            };
+            placements.push(Placement::new(x, y, tile));
+        }
+        Ok(placements)
+    }
+
+    fn score(&mut self) -> Result<Score, SaveError> {
+        let length = self.reader.read_u16::<BigEndian>()?;
+        let mut words = vec![];
+        for _ in 0..length {
+            words.push(WordScore {
+                word: self.string()?,
+                letters: self.letters()?,
+                x: self.reader.read_u32::<BigEndian>()?,
+                y: self.reader.read_u32::<BigEndian>()?,
+                direction: match self.reader.read_u8()? {
+                    0 => Direction::Across,
+                    1 => Direction::Down,
+                    direction => return Err(corrupt(format!("unknown direction {}", direction))),
+                },
+                score: self.reader.read_u32::<BigEndian>()?,
+            });
+        }
+        Ok(Score {
+            words,
+            bingo: self.reader.read_u32::<BigEndian>()?,
+            total: self.reader.read_u32::<BigEndian>()?,
+        })
+    }
+
+    fn player(&mut self) -> Result<Option<Player>, SaveError> {
+        if !self.flag()? {
+            return Ok(None);
+        }
+        let id = PlayerId::new(self.reader.read_u64::<BigEndian>()?);
+        let identity = Identity::new(id, &self.string()?)
+            .map_err(|e| corrupt(format!("a player's name isn't allowed, {}", e)))?;
+        let mut player = Player::new(identity);
+        player.score = self.reader.read_i32::<BigEndian>()?;
+        player.hand = self.letters()?;
+        player.computer = match self.reader.read_u8()? {
+            0 => None,
+            1 => Some(Difficulty::Beginner),
+            2 => Some(Difficulty::Intermediate),
+            3 => Some(Difficulty::Expert),
+            difficulty => return Err(corrupt(format!("unknown difficulty {}", difficulty))),
+        };
+        Ok(Some(player))
+    }
+
+    fn turn_state(&mut self) -> Result<TurnState, SaveError> {
+        let bag = Bag {
+            tiles: self.letters()?,
+            rng: SeededRng::new(self.reader.read_u64::<BigEndian>()?),
+        };
+        let turn = self.seat()? as u8;
+        let scoreless_turns = self.reader.read_u8()?;
+        let pending = if self.flag()? {
+            Some(PendingPlay {
+                player: self.seat()?,
+                placements: self.placements()?,
+                score: self.score()?,
+                went_out: self.flag()?,
+            })
+        } else {
+            None
+        };
+        let length = self.reader.read_u16::<BigEndian>()? as usize;
+        if length != self.game.rules.players {
+            return Err(corrupt(format!(
+                "there are {} forfeits for {} seats",
+                length, self.game.rules.players
+            )));
+        }
+        let forfeits = (0..length)
+            .map(|_| self.flag())
+            .collect::<Result<Vec<bool>, SaveError>>()?;
+        let standings = if self.flag()? {
+            let reason = match self.reader.read_u8()? {
+                0 => EndReason::WentOut(self.seat()?),
+                1 => EndReason::Scoreless,
+                reason => return Err(corrupt(format!("unknown end reason {}", reason))),
+            };
+            let length = self.reader.read_u16::<BigEndian>()?;
+            let mut standings = vec![];
+            for _ in 0..length {
+                standings.push(Standing {
+                    seat: self.seat()?,
+                    username: self.string()?,
+                    score: self.reader.read_i32::<BigEndian>()?,
+                    adjustment: self.reader.read_i32::<BigEndian>()?,
+                    time_penalty: self.reader.read_u32::<BigEndian>()?,
+                    team: if self.flag()? {
+                        Some(self.team()?)
+                    } else {
+                        None
+                    },
+                    rank: self.reader.read_u8()?,
+                });
+            }
+            Some(Standings { reason, standings })
+        } else {
+            None
+        };
+        Ok(TurnState {
+            bag,
+            turn,
+            scoreless_turns,
+            pending,
+            forfeits,
+            standings,
+        })
+    }
+
+    fn history_move(&mut self) -> Result<Move, SaveError> {
+        let player = self.seat()?;
+        let rack = self.letters()?;
+        let kind = match self.reader.read_u8()? {
+            0 => MoveKind::Play {
+                placements: self.placements()?,
+                score: self.score()?,
+            },
+            1 => MoveKind::Pass,
+            2 => MoveKind::Exchange {
+                tiles: self.letters()?,
+            },
+            3 => {
+                let challenger = self.seat()?;
+                let placements = self.placements()?;
+                let length = self.reader.read_u16::<BigEndian>()?;
+                let words = (0..length)
+                    .map(|_| self.string())
+                    .collect::<Result<Vec<String>, SaveError>>()?;
+                MoveKind::PhonyRemoved {
+                    challenger,
+                    placements,
+                    words,
+                }
+            }
+            4 => MoveKind::ChallengeFailed {
+                challenged: self.seat()?,
+                forfeit: self.flag()?,
+            },
+            5 => MoveKind::LostTurn,
+            6 => MoveKind::EndRack {
+                adjustment: self.reader.read_i32::<BigEndian>()?,
+            },
+            7 => MoveKind::TimePenalty {
+                points: self.reader.read_u32::<BigEndian>()?,
+            },
+            kind => return Err(corrupt(format!("unknown move type {}", kind))),
+        };
+        Ok(Move {
+            player,
+            rack,
+            kind,
+            score_before: self.reader.read_i32::<BigEndian>()?,
+            score_after: self.reader.read_i32::<BigEndian>()?,
+            state: self.turn_state()?,
+        })
+    }
+}
@@ -184,6 +184,21 @@ impl Cell {
         self.background_colour = colour.to_string();
     }
 
+    // Blanks are shown in lower case and flagged so they can be styled apart from real letters
+    // An empty tile clears the cell
+    pub fn set_tile(&mut self, tile: &str, blank: bool) {
+        if blank {
+            self.element.set_inner_text(&tile.to_lowercase());
+        } else {
+            self.element.set_inner_text(tile);
+        }
+        if blank && !tile.is_empty() {
+            self.element.set_attribute("data-blank", "true").unwrap();
+        } else {
+            self.element.remove_attribute("data-blank").unwrap();
+        }
+    }
+
     pub fn set_callback(&mut self, cb: Box<dyn FnMut()>) {
         let closure = Closure::wrap(cb as Box<dyn FnMut()>);
         self.element
@@ -28,7 +28,8 @@ impl ThreadPool {
 
     fn execute<F>(&self, f: F)
     where
-        F: FnOnce() + Send + 'static, {
+        F: FnOnce() + Send + 'static,
+    {
         let job = Box::new(f);
         self.sender.send(Message::NewJob(job)).unwrap();
     }
@@ -73,7 +74,6 @@ impl Worker {
                 }
                 Message::Terminate => {
                     println!("Worker {} received Termination Request!", id);
-                    return;
                 }
             }
         });
@@ -1,24 +1,133 @@
 #![allow(unused_imports)]
 use std::io::{prelude::*, Cursor};
 use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::channel;
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::Duration;
 use tungstenite::accept;
 use tungstenite::handshake::HandshakeError;
 use tungstenite::protocol::Message;
-use worders::game::GameState;
+use worders::ai::Difficulty;
+use worders::alphabet::{Alphabet, Letter};
+use worders::clock::TimeControl;
+use worders::dictionary::Dictionary;
+use worders::game::{GameState, Placement};
+use worders::identity::PlayerId;
 use worders::packets::*;
+use worders::rules::Rules;
 use worders::thread_pool::ThreadPool;
 
+mod room;
+
+use room::Room;
+
 fn main() {
     // The number of games that can run concurrently
     let _pool = ThreadPool::new(10);
-    let mut game = Arc::new(Mutex::new(GameState::new(0)));
+    // Setting WORDERS_SEED replays the draw order of a previous game
+    let seed = std::env::var("WORDERS_SEED")
+        .ok()
+        .and_then(|seed| seed.parse().ok());
+    // WORDERS_LANGUAGE picks the tiles for the game, e.g. "spanish"
+    let alphabet = std::env::var("WORDERS_LANGUAGE")
+        .ok()
+        .map(|language| Alphabet::by_name(&language).expect("Unknown language"));
+    // WORDERS_RULESET is either a preset name like "super" or the path to a ruleset file
+    let mut rules = match std::env::var("WORDERS_RULESET") {
+        Ok(ruleset) => match Rules::preset(&ruleset) {
+            Some(rules) => rules,
+            None => Rules::from_file(&ruleset).unwrap_or_else(|e| panic!("{}: {}", ruleset, e)),
+        },
+        Err(_) => Rules::default(),
+    };
+    if let Some(alphabet) = alphabet {
+        rules.alphabet = alphabet;
+    }
+    // WORDERS_SAVE is where the game is kept between restarts, it is carried on if it exists
+    let save_path = std::env::var("WORDERS_SAVE").ok();
+    let saved = save_path
+        .as_ref()
+        .filter(|path| std::path::Path::new(path).exists());
+    let mut state = match saved {
+        Some(path) => {
+            let state =
+                GameState::load_from_file(path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+            println!("Carrying on the game saved in {}", path);
+            state
+        }
+        None => {
+            println!("Using the {} ruleset", rules.name);
+            let state = GameState::new(0, rules, seed);
+            println!("Starting game with seed {}", state.seed());
+            state
+        }
+    };
+    // WORDERS_VARIANT picks how words are checked in a new game, e.g. "clabbers"
+    if let (Ok(variant), None) = (std::env::var("WORDERS_VARIANT"), saved) {
+        state.set_variant(variant.parse().expect("Invalid variant"));
+    }
+    // An optional word list can be passed as the first argument
+    if let Some(path) = std::env::args().nth(1) {
+        let dictionary = Dictionary::from_file(&path, &state.rules().alphabet)
+            .expect("Failed to load word list");
+        println!("Loaded {} words from {}", dictionary.len(), path);
+        state.set_dictionary(Arc::new(dictionary));
+    }
+    // WORDERS_COMMON_WORDS is a smaller word list beginner computer players stick to. Like the
+    // dictionary it isn't saved, so it is loaded again when a saved game is carried on.
+    if let Ok(path) = std::env::var("WORDERS_COMMON_WORDS") {
+        let common_words = Dictionary::from_file(&path, &state.rules().alphabet)
+            .expect("Failed to load common word list");
+        println!("Loaded {} common words from {}", common_words.len(), path);
+        state.set_common_words(Arc::new(common_words));
+    }
+    // WORDERS_CLOCK times a new game, e.g. "25+5" gives everyone 25 minutes and adds 5 seconds
+    // after each turn
+    if let (Ok(clock), None) = (std::env::var("WORDERS_CLOCK"), saved) {
+        let (minutes, increment) = clock.split_once('+').unwrap_or((&clock, "0"));
+        let minutes: u64 = minutes.trim().parse().expect("Invalid clock minutes");
+        let increment: u64 = increment.trim().parse().expect("Invalid clock increment");
+        state.set_time_control(Some(TimeControl::new(
+            Duration::from_secs(minutes * 60),
+            Duration::from_secs(increment),
+        )));
+    }
+    // WORDERS_TEAMS=1 makes a new game two teams, partners sit in every other seat
+    if let (Ok(teams), None) = (std::env::var("WORDERS_TEAMS"), saved) {
+        if teams == "1" && !state.set_team_play(true) {
+            panic!("Team games need an even number of seats");
+        }
+    }
+    // WORDERS_COMPUTERS seats a computer player for each difficulty listed, e.g. "beginner,expert"
+    if let (Ok(computers), None) = (std::env::var("WORDERS_COMPUTERS"), saved) {
+        for difficulty in computers.split(',') {
+            let difficulty: Difficulty = difficulty.parse().expect("Invalid computer difficulty");
+            state.add_computer(difficulty);
+        }
+    }
+    // WORDERS_HINTS limits how many hints each player can ask for
+    let hint_limit = std::env::var("WORDERS_HINTS")
+        .ok()
+        .and_then(|limit| limit.parse().ok());
+    // WORDERS_GCG is where the moves are written in GCG format after each one
+    let gcg_path = std::env::var("WORDERS_GCG").ok();
+    let mut room = Room::new(state, hint_limit, save_path, gcg_path);
+    // WORDERS_SPECTATORS caps how many people can watch, "off" stops anyone watching. It is only
+    // read at startup, there is no way to change it while the server is running.
+    match std::env::var("WORDERS_SPECTATORS").as_deref() {
+        Ok("off") => room.set_spectating(false),
+        Ok(limit) => room.set_spectator_limit(limit.parse().expect("Invalid spectator limit")),
+        Err(_) => {}
+    }
+    // WORDERS_RATINGS is the rating history file finished games are added to
+    room.set_ratings_path(std::env::var("WORDERS_RATINGS").ok());
+    let room = Arc::new(Mutex::new(room));
     let server = TcpListener::bind("192.168.0.14:8080").unwrap();
-    let game_clone = game.clone();
     for stream in server.incoming() {
+        let room_clone = room.clone();
         thread::spawn(move || match stream {
-            Ok(stream) => match handle_websocket(stream, game_clone) {
+            Ok(stream) => match handle_websocket(stream, room_clone) {
                 Ok(_) => {}
                 Err(e) => {
                     println!("Error Occured: {}", e);
@@ -31,27 +140,58 @@ fn main() {
     }
 }
 
+// Gives up the client's place in the room however the connection ends, including when a bad
+// packet panics part way through decoding, so their seat can be taken back
+struct Connection {
+    room: Arc<Mutex<Room>>,
+    client: usize,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        let mut room = match self.room.lock() {
+            Ok(room) => room,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        room.leave(self.client);
+    }
+}
+
 fn handle_websocket(
     stream: TcpStream,
-    game: Arc<Mutex<GameState>>,
+    room: Arc<Mutex<Room>>,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    // Reads time out so that packets queued for this client by other connections get sent
+    stream.set_read_timeout(Some(Duration::from_millis(50)))?;
     match accept(stream) {
         Ok(mut ws) => {
-            let active_player = 0;
-            loop {
+            let (sender, receiver) = channel::<Vec<u8>>();
+            let client = {
+                let mut room = room.lock().unwrap();
+                // Clients are sent the game once they say who they are or ask to spectate
+                let client = room.join(sender);
+                room.send_alphabet(client);
+                client
+            };
+            let _connection = Connection {
+                room: room.clone(),
+                client,
+            };
+            let mut pending: Vec<Placement> = vec![];
+            let result: Result<(), Box<dyn std::error::Error>> = 'connection: loop {
+                room.lock().unwrap().update_clocks();
+                while let Ok(bytes) = receiver.try_recv() {
+                    if let Err(e) = ws.write_message(Message::Binary(bytes)) {
+                        break 'connection Err(Box::new(e));
+                    }
+                }
                 match ws.read_message() {
                     Ok(msg) => match msg {
                         Message::Binary(bytes) => {
                             let mut cursor = Cursor::new(bytes.as_slice());
                             let packet = Packets::decode(&mut cursor);
-                            match packet {
-                                Packets::Ack(ack) => println!("{:?}", ack),
-                                Packets::PlayerState(state) => println!("{:?}", state),
-                                Packets::Place(place) => println!("{:?}", place),
-                                Packets::GameState(state) => println!("{:?}", state),
-                                Packets::Unknown => eprint!("Unknown Packet Received"),
-                            }
-                            //ws.write_message(Message::Binary(send_buffer)).unwrap();
+                            let mut room = room.lock().unwrap();
+                            handle_packet(&mut room, client, &mut pending, packet);
                         }
                         _ => {
                             println!("Message wasn't Binary: {:?}", msg);
@@ -60,20 +200,174 @@ fn handle_websocket(
                     Err(e) => {
                         use tungstenite::error::Error as TError;
                         match e {
+                            TError::Io(ref io)
+                                if io.kind() == std::io::ErrorKind::WouldBlock
+                                    || io.kind() == std::io::ErrorKind::TimedOut =>
+                            {
+                                continue;
+                            }
                             TError::ConnectionClosed | TError::AlreadyClosed => {
                                 println!("User Disconnected!");
-                                break;
+                                break Ok(());
                             }
                             _ => {}
                         }
-                        return Err(Box::new(e));
+                        break Err(Box::new(e));
                     }
                 }
+            };
+            result
+        }
+        Err(e) => Err(Box::new(e)),
+    }
+}
+
+fn handle_packet(room: &mut Room, client: usize, pending: &mut Vec<Placement>, packet: Packets) {
+    // The time up to now belongs to whoever's turn it was before this packet
+    room.tick();
+    if let Packets::Join(join) = &packet {
+        let team = join.team().checked_sub(1).map(|team| team as usize);
+        match room.sit(
+            client,
+            PlayerId::new(*join.identity()),
+            join.username(),
+            team,
+        ) {
+            Ok(_) => {
+                room.acknowledge(client, AckState::Success, String::new());
+                room.send_board(client);
+                room.run_computer_turns();
+                room.save();
+                room.broadcast_players();
+                room.broadcast_clocks();
+                // Computer players can finish the game as soon as someone sits down
+                if room.game.is_over() {
+                    room.rate();
+                    room.broadcast_standings();
+                } else {
+                    room.announce_turn();
+                }
+            }
+            Err(reason) => room.acknowledge(client, AckState::Failure, reason),
+        }
+        return;
+    }
+    if let Packets::Spectate(_) = &packet {
+        match room.spectate(client) {
+            Ok(()) => {
+                room.acknowledge(client, AckState::Success, String::new());
+                room.send_board(client);
+                room.broadcast_players();
+                room.broadcast_clocks();
+                room.send_standings(client);
+            }
+            Err(reason) => room.acknowledge(client, AckState::Failure, reason),
+        }
+        return;
+    }
+    let seat = match room.seat(client) {
+        Some(seat) => seat,
+        None => {
+            room.acknowledge(client, AckState::Failure, "You are not seated".to_string());
+            return;
+        }
+    };
+    let result = match packet {
+        Packets::Ack(ack) => match ack.state() {
+            AckState::Confirm => room.game.play(seat, pending).map(|_| {
+                room.broadcast_placements(pending);
+                pending.clear();
+            }),
+            _ => {
+                println!("{:?}", ack);
+                return;
+            }
+        },
+        Packets::Place(place) => {
+            let placement = Placement::from(&place);
+            pending.retain(|p| p.x != placement.x || p.y != placement.y);
+            // A blank with no letter is sent when a tile is picked back up
+            if *place.tile() != Letter::BLANK {
+                pending.push(placement);
+            }
+            return;
+        }
+        Packets::Pass(_) => room.game.pass(seat),
+        Packets::Undo(_) => {
+            if let Err(reason) = room.undo(seat) {
+                room.acknowledge(client, AckState::Failure, reason);
+                return;
             }
             Ok(())
         }
-        Err(e) => {
-            return Err(Box::new(e));
+        Packets::HintRequest(request) => {
+            if let Err(reason) = room.send_hints(client, seat, *request.count()) {
+                room.acknowledge(client, AckState::Failure, reason);
+            }
+            return;
+        }
+        Packets::Challenge(challenge) => {
+            if *challenge.challenge() {
+                let played = room.game.pending_play().cloned();
+                room.game.challenge(seat).map(|outcome| {
+                    if let Some(played) = played {
+                        room.broadcast_challenge(&played, seat, &outcome);
+                    }
+                })
+            } else {
+                room.game.accept_play(seat)
+            }
+        }
+        Packets::Exchange(exchange) => room.game.exchange(seat, exchange.tiles()),
+        Packets::PlayerState(state) => {
+            println!("{:?}", state);
+            return;
+        }
+        Packets::GameState(state) => {
+            println!("{:?}", state);
+            return;
+        }
+        Packets::FinalStanding(standing) => {
+            println!("{:?}", standing);
+            return;
+        }
+        Packets::ChallengeResult(result) => {
+            println!("{:?}", result);
+            return;
+        }
+        Packets::Hint(hint) => {
+            println!("{:?}", hint);
+            return;
+        }
+        Packets::AlphabetLetter(letter) => {
+            println!("{:?}", letter);
+            return;
+        }
+        Packets::ClockState(clock) => {
+            println!("{:?}", clock);
+            return;
+        }
+        // Handled before the seat check
+        Packets::Join(_) | Packets::Spectate(_) => return,
+        Packets::Unknown => {
+            eprint!("Unknown Packet Received");
+            return;
+        }
+    };
+    match result {
+        Ok(()) => {
+            room.acknowledge(client, AckState::Success, String::new());
+            room.run_computer_turns();
+            room.save();
+            room.broadcast_players();
+            room.broadcast_clocks();
+            if room.game.is_over() {
+                room.rate();
+                room.broadcast_standings();
+            } else {
+                room.announce_turn();
+            }
         }
+        Err(e) => room.acknowledge(client, AckState::Failure, e.to_string()),
     }
 }
@@ -0,0 +1,518 @@
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+use worders::ai::hints;
+use worders::alphabet::Letter;
+use worders::game::{
+    Action, ChallengeOutcome, Direction, GameState, PendingPlay, Placement, Player, Tile,
+};
+use worders::history::MoveKind;
+use worders::identity::{Identity, PlayerId};
+use worders::packets::*;
+use worders::ratings::Ratings;
+
+// Clients only get the alphabet until they sit down or start spectating
+pub struct Client {
+    pub seat: Option<usize>,
+    // Spectators are sent everything players are except for racks
+    pub spectator: bool,
+    sender: Sender<Vec<u8>>,
+}
+
+impl Client {
+    fn is_watching(&self) -> bool {
+        self.seat.is_some() || self.spectator
+    }
+}
+
+pub struct Room {
+    pub game: GameState,
+    clients: Vec<Option<Client>>,
+    // How many hints each seat can ask for during the game, unlimited if not set
+    hint_limit: Option<u32>,
+    hints_used: Vec<u32>,
+    // Where the game is written after every move so it survives a restart
+    save_path: Option<String>,
+    // Where the move log is written in GCG format for other tools to read
+    gcg_path: Option<String>,
+    // When the clocks were last run and last sent out
+    ticked: Instant,
+    clocks_sent: Instant,
+    spectating: bool,
+    spectator_limit: usize,
+    // Where the ladder's rating history is kept, finished games are rated once
+    ratings_path: Option<String>,
+    rated: bool,
+}
+
+pub const DEFAULT_SPECTATOR_LIMIT: usize = 20;
+
+// How often clients are sent the clocks while a timed game is being played
+const CLOCK_INTERVAL: Duration = Duration::from_secs(1);
+
+impl Room {
+    pub fn new(
+        game: GameState,
+        hint_limit: Option<u32>,
+        save_path: Option<String>,
+        gcg_path: Option<String>,
+    ) -> Self {
+        let seats = game.seats();
+        let game_over = game.is_over();
+        Room {
+            game,
+            clients: vec![],
+            hint_limit,
+            hints_used: vec![0; seats],
+            save_path,
+            gcg_path,
+            ticked: Instant::now(),
+            clocks_sent: Instant::now(),
+            spectating: true,
+            spectator_limit: DEFAULT_SPECTATOR_LIMIT,
+            ratings_path: None,
+            rated: game_over,
+        }
+    }
+
+    pub fn set_ratings_path(&mut self, path: Option<String>) {
+        self.ratings_path = path;
+    }
+
+    // Adds the game to the ratings file once it has finished. The file is read again each time
+    // so other servers and hand edits aren't overwritten.
+    pub fn rate(&mut self) {
+        let path = match &self.ratings_path {
+            Some(path) if self.game.is_over() && !self.rated => path,
+            _ => return,
+        };
+        self.rated = true;
+        let mut ratings = if std::path::Path::new(path).exists() {
+            match Ratings::from_file(path) {
+                Ok(ratings) => ratings,
+                Err(e) => {
+                    println!("Failed to read the ratings in {}: {}", path, e);
+                    return;
+                }
+            }
+        } else {
+            Ratings::new()
+        };
+        match ratings.record(&self.game) {
+            Ok(changes) => {
+                for change in changes {
+                    println!(
+                        "{} is now rated {:.0} ({:+.0})",
+                        change.name,
+                        change.after,
+                        change.after - change.before
+                    );
+                }
+            }
+            Err(e) => {
+                println!("The game wasn't rated: {}", e);
+                return;
+            }
+        }
+        if let Err(e) = ratings.save_to_file(path) {
+            println!("Failed to save the ratings to {}: {}", path, e);
+        }
+    }
+
+    pub fn set_spectator_limit(&mut self, limit: usize) {
+        self.spectator_limit = limit;
+    }
+
+    // Like the spectator limit this is set from the environment before anyone connects
+    pub fn set_spectating(&mut self, spectating: bool) {
+        self.spectating = spectating;
+    }
+
+    pub fn spectate(&mut self, client: usize) -> Result<(), String> {
+        if !self.spectating {
+            return Err("This game can't be watched".to_string());
+        }
+        if self.seat(client).is_some() {
+            return Err("You are playing in this game".to_string());
+        }
+        if self
+            .clients
+            .get(client)
+            .and_then(|c| c.as_ref())
+            .is_some_and(|c| c.spectator)
+        {
+            return Err("You are already watching".to_string());
+        }
+        let watching = self
+            .clients
+            .iter()
+            .flatten()
+            .filter(|c| c.spectator)
+            .count();
+        if watching >= self.spectator_limit {
+            return Err(format!(
+                "The game already has {} spectators",
+                self.spectator_limit
+            ));
+        }
+        if let Some(Some(client)) = self.clients.get_mut(client) {
+            client.spectator = true;
+        }
+        Ok(())
+    }
+
+    pub fn save(&self) {
+        if let Some(path) = &self.save_path {
+            if let Err(e) = self.game.save_to_file(path) {
+                println!("Failed to save the game to {}: {}", path, e);
+            }
+        }
+        if let Some(path) = &self.gcg_path {
+            if let Err(e) = std::fs::write(path, self.game.to_gcg()) {
+                println!("Failed to write the move log to {}: {}", path, e);
+            }
+        }
+    }
+
+    // Charges the time since the last tick to the player whose turn it is
+    pub fn tick(&mut self) {
+        let now = Instant::now();
+        self.game.run_clock(now - self.ticked);
+        self.ticked = now;
+    }
+
+    // Called regularly by every connection, the clocks are only sent out once per interval
+    pub fn update_clocks(&mut self) {
+        self.tick();
+        let timed = self.game.time_control().is_some() && !self.game.is_over();
+        if timed && self.clocks_sent.elapsed() >= CLOCK_INTERVAL {
+            self.broadcast_clocks();
+        }
+    }
+
+    pub fn broadcast_clocks(&mut self) {
+        self.clocks_sent = Instant::now();
+        let current = self.game.current_player();
+        for seat in 0..self.game.seats() {
+            if let (Some(clock), Some(_)) = (self.game.clock(seat), self.game.player(seat)) {
+                let packet = ClockState::new(
+                    self.id(),
+                    seat as u8,
+                    clock.remaining().as_millis() as u32,
+                    clock.overtime().as_millis() as u32,
+                    seat == current && !self.game.is_over(),
+                );
+                self.broadcast(Packets::ClockState(packet));
+            }
+        }
+    }
+
+    pub fn join(&mut self, sender: Sender<Vec<u8>>) -> usize {
+        let client = Some(Client {
+            seat: None,
+            spectator: false,
+            sender,
+        });
+        if let Some(index) = self.clients.iter().position(|c| c.is_none()) {
+            self.clients[index] = client;
+            index
+        } else {
+            self.clients.push(client);
+            self.clients.len() - 1
+        }
+    }
+
+    pub fn leave(&mut self, client: usize) {
+        if let Some(slot) = self.clients.get_mut(client) {
+            *slot = None;
+        }
+    }
+
+    pub fn seat(&self, client: usize) -> Option<usize> {
+        self.clients
+            .get(client)
+            .and_then(|c| c.as_ref())
+            .and_then(|c| c.seat)
+    }
+
+    // Seats the client as the given player, on the team they asked for if there is one. Someone
+    // who already has a seat, because they lost their connection or the server restarted, gets
+    // it back with the same rack.
+    pub fn sit(
+        &mut self,
+        client: usize,
+        id: PlayerId,
+        name: &str,
+        team: Option<usize>,
+    ) -> Result<usize, String> {
+        if self.seat(client).is_some() {
+            return Err("You already have a seat".to_string());
+        }
+        if id.is_computer() {
+            return Err("That id belongs to a computer player".to_string());
+        }
+        let identity = Identity::new(id, name).map_err(|e| e.to_string())?;
+        let seat = match self.game.seat_of(id) {
+            Some(seat) if self.clients.iter().flatten().any(|c| c.seat == Some(seat)) => {
+                return Err("You are already playing from somewhere else".to_string())
+            }
+            Some(seat) => seat,
+            None => match team {
+                Some(team) => self.game.add_player_to_team(Player::new(identity), team),
+                None => self.game.add_player(Player::new(identity)),
+            }
+            .map_err(|e| e.to_string())?,
+        };
+        if let Some(Some(client)) = self.clients.get_mut(client) {
+            client.seat = Some(seat);
+            client.spectator = false;
+        }
+        Ok(seat)
+    }
+
+    fn id(&self) -> u16 {
+        self.game.id() as u16
+    }
+
+    pub fn send(&self, client: usize, packet: Packets) {
+        if let Some(Some(client)) = self.clients.get(client) {
+            let _ = client.sender.send(encode(packet));
+        }
+    }
+
+    pub fn broadcast(&self, packet: Packets) {
+        let bytes = encode(packet);
+        for client in self.clients.iter().flatten().filter(|c| c.is_watching()) {
+            let _ = client.sender.send(bytes.clone());
+        }
+    }
+
+    pub fn acknowledge(&self, client: usize, state: AckState, reason: String) {
+        self.send(client, Packets::Ack(Ack::new(self.id(), state, reason)));
+    }
+
+    // Clients only get letter numbers in the other packets, so they are told how to show each
+    // one when they join
+    pub fn send_alphabet(&self, client: usize) {
+        let alphabet = &self.game.rules().alphabet;
+        for letter in alphabet.letters() {
+            let packet = AlphabetLetter::new(
+                self.id(),
+                letter,
+                alphabet.display(letter).to_string(),
+                alphabet.score(letter),
+            );
+            self.send(client, Packets::AlphabetLetter(packet));
+        }
+    }
+
+    // Tiles already on the board for clients joining a game part way through
+    pub fn send_board(&self, client: usize) {
+        for (x, y, tile) in self.game.board().tiles() {
+            let place = Place::new(self.id(), tile.letter, tile.blank, x, y);
+            self.send(client, Packets::Place(place));
+        }
+    }
+
+    // Everyone gets every player's score, but only the owner of a hand and their partners are
+    // sent its tiles
+    pub fn broadcast_players(&self) {
+        for (index, client) in self.clients.iter().enumerate() {
+            if let Some(client) = client.as_ref().filter(|c| c.is_watching()) {
+                for seat in 0..self.game.seats() {
+                    if let Some(player) = self.game.player(seat) {
+                        let shown = client
+                            .seat
+                            .is_some_and(|own| self.game.same_team(own, seat));
+                        let tiles = if shown {
+                            player.hand().to_vec()
+                        } else {
+                            vec![]
+                        };
+                        let state = PlayerState::new(
+                            self.id(),
+                            seat as u8,
+                            player.id().value(),
+                            player.username().to_string(),
+                            team_number(self.game.team_of(seat)),
+                            tiles,
+                            player.score(),
+                        );
+                        self.send(index, Packets::PlayerState(state));
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn broadcast_placements(&self, placements: &[Placement]) {
+        for placement in placements {
+            let place = Place::new(
+                self.id(),
+                placement.tile.letter,
+                placement.tile.blank,
+                placement.x,
+                placement.y,
+            );
+            self.broadcast(Packets::Place(place));
+        }
+    }
+
+    // A phony play is taken back off every client's board
+    pub fn broadcast_challenge(
+        &self,
+        played: &PendingPlay,
+        challenger: usize,
+        outcome: &ChallengeOutcome,
+    ) {
+        let words = match outcome {
+            ChallengeOutcome::Phony { words } => {
+                let removed: Vec<Placement> = played
+                    .placements
+                    .iter()
+                    .map(|p| Placement::new(p.x, p.y, Tile::new(Letter::BLANK)))
+                    .collect();
+                self.broadcast_placements(&removed);
+                words.join(", ")
+            }
+            ChallengeOutcome::Valid { .. } => String::new(),
+        };
+        let result = ChallengeResult::new(
+            self.id(),
+            played.player as u8,
+            challenger as u8,
+            !words.is_empty(),
+            words,
+        );
+        self.broadcast(Packets::ChallengeResult(result));
+    }
+
+    // Lets any computer players take their turns until it is a person's turn again. The time
+    // computers spend thinking isn't charged to anyone.
+    pub fn run_computer_turns(&mut self) {
+        while let Some(action) = self.game.play_computer_turn() {
+            if let Action::Play(placements) = action {
+                self.broadcast_placements(&placements);
+            }
+        }
+        self.ticked = Instant::now();
+    }
+
+    // Only allowed when everyone else at the table is a computer. Moves are taken back until the
+    // seat's own last move has been undone, so the computers don't just make theirs again.
+    pub fn undo(&mut self, seat: usize) -> Result<(), String> {
+        let practice = (0..self.game.seats())
+            .filter(|other| *other != seat)
+            .all(|other| self.game.player(other).is_none_or(|p| p.is_computer()));
+        if !practice {
+            return Err("Moves can only be taken back in practice games".to_string());
+        }
+        if self.game.history().iter().all(|m| m.made_by() != seat) {
+            return Err("You have no moves to take back".to_string());
+        }
+        loop {
+            let undone = self.game.undo_last();
+            for undone in &undone {
+                match &undone.kind {
+                    MoveKind::Play { placements, .. } => {
+                        let removed: Vec<Placement> = placements
+                            .iter()
+                            .map(|p| Placement::new(p.x, p.y, Tile::new(Letter::BLANK)))
+                            .collect();
+                        self.broadcast_placements(&removed);
+                    }
+                    MoveKind::PhonyRemoved { placements, .. } => {
+                        self.broadcast_placements(placements)
+                    }
+                    _ => {}
+                }
+            }
+            match undone.last() {
+                Some(last) if last.made_by() != seat => continue,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    pub fn send_hints(&mut self, client: usize, seat: usize, count: u8) -> Result<(), String> {
+        if let Some(limit) = self.hint_limit {
+            if self.hints_used[seat] >= limit {
+                return Err("You have no hints left this game".to_string());
+            }
+        }
+        if self.game.dictionary().is_none() {
+            return Err("This game has no word list to find hints with".to_string());
+        }
+        self.hints_used[seat] += 1;
+        let hints = hints(&self.game, seat, count as usize);
+        for (rank, play) in hints.iter().enumerate() {
+            if let Some(word) = play.score.words.first() {
+                let hint = Hint::new(
+                    self.id(),
+                    seat as u8,
+                    rank as u8 + 1,
+                    word.word.clone(),
+                    word.x,
+                    word.y,
+                    word.direction == Direction::Across,
+                    play.score.total,
+                );
+                self.send(client, Packets::Hint(hint));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn broadcast_standings(&self) {
+        for packet in self.standings() {
+            self.broadcast(packet);
+        }
+    }
+
+    // For spectators arriving after the game has finished
+    pub fn send_standings(&self, client: usize) {
+        for packet in self.standings() {
+            self.send(client, packet);
+        }
+    }
+
+    fn standings(&self) -> Vec<Packets> {
+        let standings = match self.game.standings() {
+            Some(standings) => &standings.standings,
+            None => return vec![],
+        };
+        standings
+            .iter()
+            .map(|standing| {
+                Packets::FinalStanding(FinalStanding::new(
+                    self.id(),
+                    standing.seat as u8,
+                    standing.username.clone(),
+                    team_number(standing.team),
+                    standing.score,
+                    standing.adjustment,
+                    standing.time_penalty,
+                    standing.rank,
+                ))
+            })
+            .collect()
+    }
+
+    pub fn announce_turn(&self) {
+        let current = self.game.current_player();
+        for (index, client) in self.clients.iter().enumerate() {
+            if client.as_ref().and_then(|c| c.seat) == Some(current) {
+                self.acknowledge(index, AckState::Turn, String::new());
+            }
+        }
+    }
+}
+
+fn team_number(team: Option<usize>) -> u8 {
+    team.map_or(0, |team| team as u8 + 1)
+}
+
+fn encode(packet: Packets) -> Vec<u8> {
+    let mut buffer = vec![];
+    packet.encode(&mut buffer).unwrap();
+    buffer
+}